@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{unbounded, Sender};
+use log::{debug, error, warn};
+use rodio::{Decoder, OutputStream, Source};
+
+/// The same sound is not started again if it was started less than this long ago. Stops held or
+/// repeating actions from stacking sounds on top of each other.
+const REPLAY_COOLDOWN: Duration = Duration::from_millis(150);
+
+/// Plays short sound files on a dedicated thread.
+pub struct SoundPlayer {
+    sender: Sender<PathBuf>,
+}
+
+impl SoundPlayer {
+    pub fn new() -> Self {
+        let (sender, receiver) = unbounded::<PathBuf>();
+
+        thread::spawn(move || {
+            // The output stream cannot be sent between threads so it must be created here
+            let (_stream, handle) = match OutputStream::try_default() {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("Unable to open audio output: {e}");
+                    return;
+                }
+            };
+
+            let mut limiter = ReplayLimiter::default();
+
+            for path in receiver.iter() {
+                if !limiter.allows(&path, Instant::now()) {
+                    continue;
+                }
+
+                let source = match open_sound(&path) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        warn!("{e}");
+                        continue;
+                    }
+                };
+
+                if let Err(e) = handle.play_raw(source.convert_samples()) {
+                    warn!("Unable to play sound {}: {e}", path.display());
+                    continue;
+                }
+
+                limiter.played(path, Instant::now());
+            }
+        });
+
+        SoundPlayer { sender }
+    }
+
+    /// Queues a sound file to be played. Does not block.
+    pub fn play(&self, path: &Path) {
+        if let Err(e) = self.sender.send(path.to_path_buf()) {
+            debug!("Audio thread is not running: {e}");
+        }
+    }
+}
+
+impl Default for SoundPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Remembers when each sound was last started, see `REPLAY_COOLDOWN`.
+#[derive(Default)]
+struct ReplayLimiter {
+    last_played: HashMap<PathBuf, Instant>,
+}
+
+impl ReplayLimiter {
+    /// Whether the sound may be started at `now`.
+    fn allows(&self, path: &Path, now: Instant) -> bool {
+        match self.last_played.get(path) {
+            Some(last) => now.duration_since(*last) >= REPLAY_COOLDOWN,
+            None => true,
+        }
+    }
+
+    fn played(&mut self, path: PathBuf, at: Instant) {
+        self.last_played.insert(path, at);
+    }
+}
+
+/// Opens and decodes a sound file. The error is the warning to log.
+fn open_sound(path: &Path) -> Result<Decoder<BufReader<File>>, String> {
+    let file =
+        File::open(path).map_err(|e| format!("Unable to open sound {}: {e}", path.display()))?;
+
+    Decoder::new(BufReader::new(file))
+        .map_err(|e| format!("Unable to decode sound {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_sound_is_not_replayed_within_the_cooldown() {
+        let mut limiter = ReplayLimiter::default();
+        let click = PathBuf::from("click.wav");
+        let start = Instant::now();
+
+        assert!(limiter.allows(&click, start));
+        limiter.played(click.clone(), start);
+
+        assert!(!limiter.allows(&click, start + REPLAY_COOLDOWN / 2));
+        assert!(limiter.allows(Path::new("other.wav"), start));
+        assert!(limiter.allows(&click, start + REPLAY_COOLDOWN));
+    }
+
+    #[test]
+    fn unusable_sound_files_are_reported() {
+        match open_sound(Path::new("missing.wav")) {
+            Err(e) => assert!(e.starts_with("Unable to open sound missing.wav: "), "{e}"),
+            Ok(_) => panic!("opened a missing file"),
+        }
+
+        let not_audio = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        match open_sound(&not_audio) {
+            Err(e) => assert!(
+                e.starts_with(&format!("Unable to decode sound {}: ", not_audio.display())),
+                "{e}"
+            ),
+            Ok(_) => panic!("decoded Cargo.toml"),
+        }
+    }
+}
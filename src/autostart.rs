@@ -0,0 +1,155 @@
+//! Launches viraction when the user logs in, see `Config::run_at_startup`.
+
+use std::io;
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use std::path::Path;
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use directories::BaseDirs;
+
+use crate::PROGRAM_NAME;
+
+/// Adds or removes viraction from the programs started on login. Safe to call when already in the
+/// requested state.
+pub fn set_enabled(enabled: bool) -> io::Result<()> {
+    let exe = std::env::current_exe()?;
+
+    platform::set_enabled(enabled, &exe.to_string_lossy())
+}
+
+/// Removes a file, treating a file that is already gone as success.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Writes a file, creating its parent directory if needed.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn write_file(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(path, contents)
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn base_dirs() -> io::Result<BaseDirs> {
+    BaseDirs::new()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unable to find home directory"))
+}
+
+/// A `Run` registry value for the current user.
+#[cfg(windows)]
+mod platform {
+    use std::{io, process::Command};
+
+    use super::PROGRAM_NAME;
+
+    const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+    pub fn set_enabled(enabled: bool, exe: &str) -> io::Result<()> {
+        let mut command = Command::new("reg");
+        if enabled {
+            command.args(["add", RUN_KEY, "/v", PROGRAM_NAME, "/t", "REG_SZ", "/d"]);
+            command.arg(format!("\"{exe}\""));
+        } else {
+            command.args(["delete", RUN_KEY, "/v", PROGRAM_NAME]);
+        }
+        command.arg("/f");
+
+        let output = command.output()?;
+        // Deleting a value that does not exist fails, which is fine
+        if output.status.success() || !enabled {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ))
+        }
+    }
+}
+
+/// A `.desktop` file in `~/.config/autostart`, see the XDG autostart spec.
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::io;
+
+    use super::{base_dirs, remove_if_exists, write_file, PROGRAM_NAME};
+
+    pub fn set_enabled(enabled: bool, exe: &str) -> io::Result<()> {
+        let path = base_dirs()?
+            .config_dir()
+            .join("autostart")
+            .join(format!("{PROGRAM_NAME}.desktop"));
+
+        if !enabled {
+            return remove_if_exists(&path);
+        }
+
+        write_file(
+            &path,
+            &format!(
+                "[Desktop Entry]\nType=Application\nName={PROGRAM_NAME}\nExec=\"{exe}\"\nX-GNOME-Autostart-enabled=true\n"
+            ),
+        )
+    }
+}
+
+/// A LaunchAgent plist in `~/Library/LaunchAgents`.
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::io;
+
+    use super::{base_dirs, remove_if_exists, write_file, PROGRAM_NAME};
+
+    pub fn set_enabled(enabled: bool, exe: &str) -> io::Result<()> {
+        let label = format!("com.vpuppr.{PROGRAM_NAME}");
+        let path = base_dirs()?
+            .home_dir()
+            .join("Library/LaunchAgents")
+            .join(format!("{label}.plist"));
+
+        if !enabled {
+            return remove_if_exists(&path);
+        }
+
+        write_file(
+            &path,
+            &format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{exe}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#
+            ),
+        )
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+mod platform {
+    use std::io;
+
+    pub fn set_enabled(_enabled: bool, _exe: &str) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "starting on login is not supported on this platform",
+        ))
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use zip::{result::ZipError, write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+const MANIFEST_NAME: &str = "manifest.toml";
+/// Every name a config file can have, see `ConfigFormat`.
+const CONFIG_NAMES: &[&str] = &["config.toml", "config.json"];
+const SCRIPTS_DIR: &str = "scripts";
+
+/// The file extension used for bundles.
+pub const BUNDLE_EXTENSION: &str = "viraction";
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Zip(ZipError),
+    BadManifest(String),
+    IncompatibleVersion(String),
+    UnexpectedEntry(String),
+    /// The bundled config cannot be read or has problems
+    BadConfig(String),
+    /// Files that already exist and would be overwritten
+    Conflicts(Vec<PathBuf>),
+}
+
+impl std::error::Error for Error {}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Zip(e) => write!(f, "{}", e),
+            Error::BadManifest(e) => write!(f, "bad manifest: {}", e),
+            Error::IncompatibleVersion(v) => {
+                write!(f, "bundle was made with incompatible version {}", v)
+            }
+            Error::UnexpectedEntry(name) => write!(f, "unexpected file in bundle: {}", name),
+            Error::BadConfig(e) => write!(f, "bad config in bundle: {}", e),
+            Error::Conflicts(paths) => {
+                write!(f, "would overwrite existing files: ")?;
+                for (i, path) in paths.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", path.display())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<ZipError> for Error {
+    fn from(e: ZipError) -> Self {
+        Error::Zip(e)
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Describes where a bundle came from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Manifest {
+    version: String,
+    build_name: String,
+    git_rev: String,
+}
+
+impl Manifest {
+    fn new() -> Self {
+        Manifest {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            build_name: crate::BUILD_NAME.to_string(),
+            git_rev: crate::GIT_REV.trim().to_string(),
+        }
+    }
+
+    /// Bundles are compatible if they share a major version, or a minor version for `0.x` versions.
+    fn is_compatible(&self) -> bool {
+        let ours = env!("CARGO_PKG_VERSION").split('.').collect::<Vec<&str>>();
+        let theirs = self.version.split('.').collect::<Vec<&str>>();
+
+        match (ours.as_slice(), theirs.as_slice()) {
+            (["0", our_minor, ..], ["0", their_minor, ..]) => our_minor == their_minor,
+            ([our_major, ..], [their_major, ..]) => our_major == their_major,
+            _ => false,
+        }
+    }
+}
+
+/// Zips up the config and scripts directory in `config_dir` along with a manifest into a single file.
+pub fn export(config_dir: &Path, path: &Path) -> Result<()> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let manifest = match toml::to_string_pretty(&Manifest::new()) {
+        Ok(m) => m,
+        Err(e) => return Err(Error::BadManifest(e.to_string())),
+    };
+    zip.start_file(MANIFEST_NAME, options)?;
+    zip.write_all(manifest.as_bytes())?;
+
+    for name in CONFIG_NAMES {
+        let config_path = config_dir.join(name);
+        if config_path.exists() {
+            zip.start_file(*name, options)?;
+            zip.write_all(&std::fs::read(config_path)?)?;
+        }
+    }
+
+    let scripts_dir = config_dir.join(SCRIPTS_DIR);
+    if scripts_dir.exists() {
+        for entry in std::fs::read_dir(scripts_dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+
+            let name = format!("{SCRIPTS_DIR}/{}", entry.file_name().to_string_lossy());
+            zip.start_file(name, options)?;
+            zip.write_all(&std::fs::read(entry.path())?)?;
+        }
+    }
+
+    zip.finish()?;
+
+    Ok(())
+}
+
+/// Reads a bundled config the same way it is read on startup and checks it for problems.
+fn check_config(contents: &str, path: &Path) -> Result<()> {
+    let (config, _) = match crate::parse_config(contents, crate::ConfigFormat::from_path(path)) {
+        Ok(c) => c,
+        Err(e) => return Err(Error::BadConfig(e.to_string())),
+    };
+
+    if let Err(errors) = config.validate() {
+        let errors = errors
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<String>>();
+        return Err(Error::BadConfig(errors.join(", ")));
+    }
+
+    Ok(())
+}
+
+/// Validates a bundle and installs its config and scripts into `config_dir`.
+///
+/// If `overwrite` is `false`, nothing is installed if any file in the bundle already exists. Nothing is
+/// installed either if the bundled config cannot be read or has problems.
+pub fn import(config_dir: &Path, path: &Path, overwrite: bool) -> Result<()> {
+    let mut zip = ZipArchive::new(File::open(path)?)?;
+
+    let manifest = {
+        let mut contents = String::new();
+        zip.by_name(MANIFEST_NAME)?.read_to_string(&mut contents)?;
+
+        match toml::from_str::<Manifest>(&contents) {
+            Ok(m) => m,
+            Err(e) => return Err(Error::BadManifest(e.to_string())),
+        }
+    };
+
+    if !manifest.is_compatible() {
+        return Err(Error::IncompatibleVersion(manifest.version));
+    }
+
+    info!(
+        "Importing bundle made with {} - {} ({})",
+        manifest.build_name, manifest.git_rev, manifest.version
+    );
+
+    let mut files = vec![];
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        if file.is_dir() || file.name() == MANIFEST_NAME {
+            continue;
+        }
+
+        // Only the config and top-level scripts are allowed, anything else could be written anywhere
+        let relative_path = match file.enclosed_name() {
+            Some(p) if CONFIG_NAMES.iter().any(|name| p == Path::new(name)) => p.to_path_buf(),
+            Some(p) if p.parent() == Some(Path::new(SCRIPTS_DIR)) => p.to_path_buf(),
+            _ => return Err(Error::UnexpectedEntry(file.name().to_string())),
+        };
+
+        // Nothing is installed if the config would fail to load afterwards
+        if CONFIG_NAMES
+            .iter()
+            .any(|name| relative_path == Path::new(name))
+        {
+            let mut contents = String::new();
+            file.read_to_string(&mut contents)?;
+            check_config(&contents, &relative_path)?;
+        }
+
+        files.push((i, config_dir.join(relative_path)));
+    }
+
+    if !overwrite {
+        let conflicts = files
+            .iter()
+            .filter(|(_, p)| p.exists())
+            .map(|(_, p)| p.clone())
+            .collect::<Vec<PathBuf>>();
+        if !conflicts.is_empty() {
+            return Err(Error::Conflicts(conflicts));
+        }
+    }
+
+    for (i, target) in files.iter() {
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut contents = vec![];
+        zip.by_index(*i)?.read_to_end(&mut contents)?;
+        std::fs::write(target, contents)?;
+
+        info!("Installed {}", target.display());
+    }
+
+    Ok(())
+}
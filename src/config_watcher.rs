@@ -0,0 +1,98 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::Action;
+
+/// Notices when the config file changes by polling its modification time.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &Path) -> Self {
+        ConfigWatcher {
+            path: path.to_path_buf(),
+            modified: modified(path),
+        }
+    }
+
+    /// Whether the file was modified since the watcher was created or this was last called.
+    /// A missing file, e.g. while an editor replaces it, is not a change.
+    pub fn changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+
+        self.modified = modified;
+
+        true
+    }
+}
+
+/// Notices when files with a given extension are added to, removed from, or modified in a directory.
+pub struct DirWatcher {
+    dir: PathBuf,
+    extension: &'static str,
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl DirWatcher {
+    pub fn new(dir: &Path, extension: &'static str) -> Self {
+        DirWatcher {
+            dir: dir.to_path_buf(),
+            extension,
+            files: files(dir, extension),
+        }
+    }
+
+    /// Whether any matching file changed since the watcher was created or this was last called.
+    pub fn changed(&mut self) -> bool {
+        let files = files(&self.dir, self.extension);
+        if files == self.files {
+            return false;
+        }
+
+        self.files = files;
+
+        true
+    }
+}
+
+/// Lists the files in `dir` with `extension` and their modification times, sorted by path.
+fn files(dir: &Path, extension: &str) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let mut files = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+            .map(|path| {
+                let modified = modified(&path);
+                (path, modified)
+            })
+            .collect::<Vec<_>>(),
+        Err(_) => vec![],
+    };
+    files.sort();
+
+    files
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Compares two lists of actions, returning the actions that are only in `old` and the actions that are only
+/// in `new`. An action with any setting changed shows up in both.
+pub fn diff_actions<'a>(
+    old: &'a [Action],
+    new: &'a [Action],
+) -> (Vec<&'a Action>, Vec<&'a Action>) {
+    let removed = old.iter().filter(|a| !new.contains(a)).collect();
+    let added = new.iter().filter(|a| !old.contains(a)).collect();
+
+    (removed, added)
+}
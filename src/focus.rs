@@ -0,0 +1,76 @@
+//! Looks up which application currently has focus, for actions that only fire in a specific application.
+
+/// Returns the process name of the foreground window, e.g. `obs64.exe`.
+#[cfg(windows)]
+pub fn active_window_name() -> Option<String> {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt, path::Path};
+
+    use winapi::um::{
+        handleapi::CloseHandle,
+        processthreadsapi::OpenProcess,
+        winbase::QueryFullProcessImageNameW,
+        winnt::PROCESS_QUERY_LIMITED_INFORMATION,
+        winuser::{GetForegroundWindow, GetWindowThreadProcessId},
+    };
+
+    unsafe {
+        let window = GetForegroundWindow();
+        if window.is_null() {
+            return None;
+        }
+
+        let mut pid = 0;
+        GetWindowThreadProcessId(window, &mut pid);
+
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return None;
+        }
+
+        let mut buf = [0u16; 1024];
+        let mut len = buf.len() as u32;
+        let ok = QueryFullProcessImageNameW(process, 0, buf.as_mut_ptr(), &mut len);
+        CloseHandle(process);
+        if ok == 0 {
+            return None;
+        }
+
+        let path = OsString::from_wide(&buf[..len as usize]);
+        Path::new(&path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    }
+}
+
+/// Returns the window class of the foreground window, e.g. `obs`.
+///
+/// Asks `xdotool`, so this only works on X11 with `xdotool` installed.
+#[cfg(target_os = "linux")]
+pub fn active_window_name() -> Option<String> {
+    let output = std::process::Command::new("xdotool")
+        .args(["getactivewindow", "getwindowclassname"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// Looking up the foreground window is not supported on this platform.
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn active_window_name() -> Option<String> {
+    None
+}
+
+/// Whether `window_name` matches a focus filter. Matching is a case-insensitive substring match so
+/// `obs` matches both `obs` and `obs64.exe`.
+pub fn matches(filter: &str, window_name: &str) -> bool {
+    window_name.to_lowercase().contains(&filter.to_lowercase())
+}
@@ -1,12 +1,24 @@
+mod key_names;
+
 use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
+    collections::{hash_map::DefaultHasher, HashMap, HashSet, VecDeque},
+    fmt::Display,
     hash::{Hash, Hasher},
+    ops::{Deref, DerefMut},
     str::FromStr,
-    time::{Duration, Instant},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use livesplit_hotkey::{Hook, KeyCode};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub use key_names::{key_name_for_code, KEY_NAMES};
 
 #[derive(Debug)]
 pub enum Error {
@@ -18,9 +30,47 @@ pub enum Error {
 
     MappedKeyMissingInReverseLookup,
 
-    BadKeyCodeName,
+    BadKeyCodeName {
+        name: String,
+        suggestion: Option<String>,
+    },
+    EmptyKeyGroup(String),
     CannotRegisterHotkey(livesplit_hotkey::Error),
     CannotUnregisterHotkey(livesplit_hotkey::Error),
+
+    ListenerChannelClosed,
+    ListenerThreadPanicked,
+}
+
+impl std::error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::HookCreate => write!(f, "unable to create hotkey hook"),
+            Error::ActionAlreadyExists => write!(f, "action already exists"),
+            Error::ActionDoesNotExist(map_type) => {
+                write!(f, "action does not exist in {:?}", map_type)
+            }
+            Error::KeyNotMapped => write!(f, "key is not mapped"),
+            Error::MappedKeyMissingInReverseLookup => {
+                write!(f, "mapped key is missing in reverse lookup")
+            }
+            Error::BadKeyCodeName {
+                name,
+                suggestion: Some(suggestion),
+            } => write!(f, "unknown key '{}'; did you mean '{}'?", name, suggestion),
+            Error::BadKeyCodeName {
+                name,
+                suggestion: None,
+            } => write!(f, "unknown key '{}'", name),
+            Error::EmptyKeyGroup(name) => write!(f, "key group '{}' has no keys", name),
+            Error::CannotRegisterHotkey(e) => write!(f, "cannot register hotkey: {}", e),
+            Error::CannotUnregisterHotkey(e) => write!(f, "cannot unregister hotkey: {}", e),
+            Error::ListenerChannelClosed => write!(f, "listener channel is closed"),
+            Error::ListenerThreadPanicked => write!(f, "listener thread panicked"),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -32,56 +82,351 @@ pub enum MapType {
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// Appended to an action's name when it is turned off.
+pub const OFF_SUFFIX: &str = ":off";
+
+/// How many times creating the OS hook is attempted before giving up.
+const HOOK_CREATE_ATTEMPTS: u32 = 5;
+/// How long to wait after the first failed attempt to create the OS hook. Doubles after every attempt.
+const HOOK_CREATE_BACKOFF: Duration = Duration::from_millis(250);
+
+/// The default number of fired actions kept in the action history.
+pub const DEFAULT_HISTORY_CAPACITY: usize = 100;
+
+/// Chords the OS handles itself. These might be consumed before reaching the hook, or both fire the
+/// action and do whatever the OS does with them.
+#[cfg(target_os = "windows")]
+const RESERVED_CHORDS: &[&[&str]] = &[
+    &["AltLeft", "Tab"],
+    &["AltLeft", "F4"],
+    &["ControlLeft", "AltLeft", "Delete"],
+    &["ControlLeft", "ShiftLeft", "Escape"],
+    &["MetaLeft", "KeyD"],
+    &["MetaLeft", "KeyL"],
+    &["MetaLeft", "Tab"],
+];
+#[cfg(target_os = "macos")]
+const RESERVED_CHORDS: &[&[&str]] = &[
+    &["MetaLeft", "Tab"],
+    &["MetaLeft", "Space"],
+    &["MetaLeft", "KeyQ"],
+    &["MetaLeft", "KeyH"],
+    &["MetaLeft", "KeyM"],
+    &["ControlLeft", "MetaLeft", "KeyQ"],
+];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const RESERVED_CHORDS: &[&[&str]] = &[
+    &["AltLeft", "Tab"],
+    &["AltLeft", "F4"],
+    &["ControlLeft", "AltLeft", "Delete"],
+    &["ControlLeft", "AltLeft", "Backspace"],
+];
+
+/// A single fired action.
+///
+/// Serializes to the JSON shared by everything that reports fired actions:
+///
+/// ```json
+/// { "name": "wave", "keys": ["ControlLeft", "KeyW"], "timestamp": 1670000000000 }
+/// ```
+///
+/// where `timestamp` is in milliseconds since the unix epoch.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionEvent {
+    pub name: String,
+    pub keys: Vec<String>,
+    pub timestamp: u64,
+    #[serde(skip)]
+    pub at: Instant,
+}
+
+impl ActionEvent {
+    fn new(name: String, keys: Vec<String>) -> Self {
+        ActionEvent {
+            name,
+            keys,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+            at: Instant::now(),
+        }
+    }
+
+    /// Serializes the event to JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Every setting that controls when actions fire, in one place so it can be saved and restored as a unit.
+///
+/// Durations are (de)serialized as milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimingConfig {
+    /// How close together every key of a chord has to be pressed. Defaults to 200ms.
+    #[serde(rename = "min_elapsed_time_ms", with = "duration_ms")]
+    pub min_elapsed_time: Duration,
+    /// Lets chords be pressed one key at a time, with each key pressed within this long of the previous key.
+    /// Disabled by default.
+    #[serde(rename = "sequential_grace_ms", with = "option_duration_ms")]
+    pub sequential_grace: Option<Duration>,
+    /// Minimum time between two emissions of the same action. Defaults to zero.
+    #[serde(rename = "output_debounce_ms", with = "duration_ms")]
+    pub output_debounce: Duration,
+}
+
+impl Default for TimingConfig {
+    fn default() -> Self {
+        TimingConfig {
+            min_elapsed_time: Duration::from_millis(200),
+            sequential_grace: None,
+            output_debounce: Duration::ZERO,
+        }
+    }
+}
+
+mod duration_ms {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(d: &Duration, s: S) -> std::result::Result<S::Ok, S::Error> {
+        s.serialize_u64(d.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<Duration, D::Error> {
+        Ok(Duration::from_millis(u64::deserialize(d)?))
+    }
+}
+
+mod option_duration_ms {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        d: &Option<Duration>,
+        s: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match d {
+            Some(d) => s.serialize_some(&(d.as_millis() as u64)),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> std::result::Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(d)?.map(Duration::from_millis))
+    }
+}
+
+/// A Rust closure that is called when an action fires.
+pub type ActionHandler = Box<dyn FnMut(&ActionEvent) + Send>;
+
+/// A registered key sequence and the actions it emits.
+#[derive(Debug, Clone)]
+pub struct ActionBinding {
+    pub id: u64,
+    pub actions: Vec<String>,
+    pub keys: Vec<String>,
+    /// Whether the keys must be pressed in order. See `HotkeyListener::register_sequence`.
+    pub ordered: bool,
+    /// Action name -> description, for the actions that have one. See `HotkeyListener::set_action_description`.
+    pub descriptions: HashMap<String, String>,
+}
+
+/// Where a binding was registered from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindingSource {
+    /// Loaded from the config file. These are persistent.
+    Config,
+    /// Registered by a script at runtime. These are lost on restart.
+    Script,
+}
+
+/// How a key sequence is matched besides its keys.
+#[derive(Debug, Clone, Default)]
+struct MappingOptions {
+    ordered: bool,
+    window: Option<Duration>,
+    /// Number of taps and the time they must happen within
+    taps: Option<(u32, Duration)>,
+    cooldown: Option<Duration>,
+}
+
+/// Keys recorded by `HotkeyListener::start_capture`.
+#[derive(Debug, Clone)]
+struct Capture {
+    keys: Vec<KeyCode>,
+    /// When the first key was captured
+    first: Option<Instant>,
+}
+
+/// Recent presses for a key sequence that fires on multiple taps.
+#[derive(Debug, Clone)]
+struct Taps {
+    count: u32,
+    within: Duration,
+    presses: VecDeque<Instant>,
+}
+
 /// Stores all actions associated with a key sequence along with the last-pressed time for each key.
+///
+/// The key sequence is made up of slots. A slot is satisfied by any one of its keys, so a plain key
+/// is just a slot with a single key while a key group is a slot with multiple keys.
+///
+/// If the mapping is ordered, the slots must be pressed in the order they are stored in. If the mapping has taps,
+/// its key must be pressed a number of times instead.
 #[derive(Debug, Clone)]
 pub struct ActionMapping {
     actions: Vec<String>,
+    sources: HashMap<String, BindingSource>,
     keys: HashMap<KeyCode, Instant>,
+    slots: Vec<Vec<KeyCode>>,
+    key_names: Vec<String>,
+    ordered: bool,
+    /// Overrides the listener's `min_elapsed_time` for this key sequence
+    window: Option<Duration>,
+    taps: Option<Taps>,
+    /// Actions that stay registered but are not emitted
+    disabled: HashSet<String>,
+    /// Minimum time between two firings of the key sequence
+    cooldown: Duration,
+    last_fired: Option<Instant>,
 }
 
 impl ActionMapping {
-    fn new(keys: &[KeyCode]) -> Self {
+    fn new(slots: &[Vec<KeyCode>], key_names: &[String], options: &MappingOptions) -> Self {
         let mut hm = HashMap::new();
         let offset = Duration::from_secs(60);
-        for key in keys.iter() {
-            hm.insert(key.clone(), Instant::now() - offset);
+        for key in slots.iter().flatten() {
+            hm.insert(*key, Instant::now() - offset);
         }
 
         ActionMapping {
             actions: vec![],
+            sources: HashMap::new(),
             keys: hm,
+            slots: slots.to_vec(),
+            key_names: key_names.to_vec(),
+            ordered: options.ordered,
+            window: options.window,
+            taps: options.taps.map(|(count, within)| Taps {
+                count,
+                within,
+                presses: VecDeque::with_capacity(count as usize),
+            }),
+            disabled: HashSet::new(),
+            cooldown: options.cooldown.unwrap_or_default(),
+            last_fired: None,
         }
     }
 
     /// Update the last pressed time for a given keycode.
-    /// Panics if the key does not exist as this should not be possible.
-    fn press_key(&mut self, key: &KeyCode) {
+    /// Errors if the key is not part of the key sequence, which means the reverse lookup is out of sync.
+    fn press_key(&mut self, key: &KeyCode) -> Result<()> {
+        self.press_key_at(key, Instant::now())
+    }
+
+    /// Same as `press_key` but with the given press time.
+    fn press_key_at(&mut self, key: &KeyCode, at: Instant) -> Result<()> {
         match self.keys.get_mut(key) {
-            Some(time) => *time = Instant::now(),
-            None => unreachable!(),
+            Some(time) => *time = at,
+            None => return Err(Error::KeyNotMapped),
+        }
+
+        if let Some(taps) = self.taps.as_mut() {
+            taps.presses.push_back(at);
+            if taps.presses.len() > taps.count as usize {
+                taps.presses.pop_front();
+            }
         }
+
+        Ok(())
     }
 
-    /// Iterates through every single key's timestamp and compares it to the passed
-    /// `min_elapsed_time`. If all timestamps are less tan the `min_elapsed_time`,
-    /// then the Action is considered to be pressed.
-    fn is_pressed(&self, min_elapsed_time: &Duration) -> bool {
-        for time in self.keys.values() {
-            if time.elapsed() > *min_elapsed_time {
+    /// Records that the key sequence fired at `at`. Returns `false` without recording anything if it
+    /// fired less than the cooldown ago.
+    fn try_fire(&mut self, at: Instant) -> bool {
+        if let Some(last) = self.last_fired {
+            if at.saturating_duration_since(last) < self.cooldown {
                 return false;
             }
         }
 
+        self.last_fired = Some(at);
         true
     }
 
+    /// Forgets recorded taps so the next press starts a new count. Called after the mapping fires.
+    fn reset_taps(&mut self) {
+        if let Some(taps) = self.taps.as_mut() {
+            taps.presses.clear();
+        }
+    }
+
+    /// Checks if every slot was pressed within `min_elapsed_time` of each other. The mapping's own window is
+    /// used instead of `min_elapsed_time` if it has one.
+    ///
+    /// The most recent press of each slot is used, and the Action is considered to be pressed if the
+    /// spread between the earliest and latest of those presses is at most `min_elapsed_time`. This means
+    /// the window is measured between the keys themselves rather than from now, e.g. with a 200ms window
+    /// pressing `A`, then `B` 150ms later, then `C` 150ms after that fires `B + C` but not `A + B + C`.
+    ///
+    /// If `sequential_grace` is set, the Action is also considered to be pressed if each press is within
+    /// `sequential_grace` of the previous one, no matter the total spread.
+    ///
+    /// If the mapping is ordered, the most recent presses must also be in slot order.
+    ///
+    /// If the mapping has taps, it is pressed once the last `count` presses all happened within `within`.
+    fn is_pressed(&self, min_elapsed_time: &Duration, sequential_grace: Option<&Duration>) -> bool {
+        if let Some(taps) = self.taps.as_ref() {
+            return taps.presses.len() == taps.count as usize
+                && match (taps.presses.front(), taps.presses.back()) {
+                    (Some(first), Some(last)) => last.duration_since(*first) <= taps.within,
+                    _ => false,
+                };
+        }
+
+        let mut times = vec![];
+        for slot in self.slots.iter() {
+            match slot.iter().filter_map(|key| self.keys.get(key)).max() {
+                Some(t) => times.push(*t),
+                None => return false,
+            }
+        }
+
+        let min_elapsed_time = self.window.as_ref().unwrap_or(min_elapsed_time);
+
+        if self.ordered && times.windows(2).any(|w| w[0] >= w[1]) {
+            return false;
+        }
+        times.sort();
+
+        let (earliest, latest) = match (times.first(), times.last()) {
+            (Some(e), Some(l)) => (e, l),
+            _ => return false,
+        };
+
+        if latest.duration_since(*earliest) <= *min_elapsed_time {
+            return true;
+        }
+
+        match sequential_grace {
+            Some(grace) => times
+                .windows(2)
+                .all(|w| w[1].duration_since(w[0]) <= *grace),
+            None => false,
+        }
+    }
+
     /// Adds an action to be emitted when all hotkeys are pressed.
-    fn add_action(&mut self, action: &String) -> Result<()> {
+    fn add_action(&mut self, action: &String, source: BindingSource) -> Result<()> {
         if self.actions.contains(action) {
             return Err(Error::ActionAlreadyExists);
         }
 
         self.actions.push(action.clone());
+        self.sources.insert(action.clone(), source);
 
         Ok(())
     }
@@ -93,36 +438,110 @@ impl ActionMapping {
         }
 
         self.actions.retain(|a| a != action);
+        self.sources.remove(action);
+        self.disabled.remove(action);
 
         Ok(())
     }
+
+    /// Renames an action in place, keeping its source and whether it is disabled.
+    fn rename_action(&mut self, old: &str, new: &str) {
+        for action in self.actions.iter_mut() {
+            if action == old {
+                *action = new.to_string();
+            }
+        }
+        if let Some(source) = self.sources.remove(old) {
+            self.sources.insert(new.to_string(), source);
+        }
+        if self.disabled.remove(old) {
+            self.disabled.insert(new.to_string());
+        }
+    }
 }
 
 /// Listens for hotkeys being pressed. If a registered sequence of keys is pressed within a minimum amount of time,
 /// then the actions associated with the key sequence is emitted.
+///
+/// `HotkeyListener` does no locking of its own. If it is shared between threads, the whole listener must be
+/// behind a single lock (e.g. `Arc<Mutex<HotkeyListener>>`) that is held for the entirety of each call.
+/// Registering and unregistering never hold a partially updated state across calls.
 pub struct HotkeyListener {
     hook: Hook,
 
     actions: HashMap<u64, ActionMapping>,
     reverse_lookup: HashMap<KeyCode, Vec<u64>>,
 
-    min_elapsed_time: Duration,
+    key_groups: HashMap<String, Vec<KeyCode>>,
+
+    timing: TimingConfig,
+
+    history: VecDeque<ActionEvent>,
+    history_capacity: usize,
+    /// Action name -> number of times it was emitted
+    trigger_counts: HashMap<String, u64>,
+
+    action_output_debounce: HashMap<String, Duration>,
+    last_emitted: HashMap<String, Instant>,
+
+    actions_with_effects: HashSet<String>,
+    warned_no_effect: HashSet<String>,
+
+    /// Action name -> exclusive group name
+    exclusive_groups: HashMap<String, String>,
+    /// Exclusive group name -> currently active action name and the keys that activated it
+    active_exclusive: HashMap<String, (String, Vec<String>)>,
+    /// Action name -> filter the focused application must match
+    focus_filters: HashMap<String, String>,
+    /// Action name -> human readable description
+    descriptions: HashMap<String, String>,
+
+    last_health_check: Instant,
+    hook_health_threshold: Option<Duration>,
+    hook_healthy: bool,
+
+    passthrough: Option<Sender<KeyCode>>,
+    /// Keys that are only hooked for passthrough or capturing
+    passthrough_keys: HashSet<KeyCode>,
+
+    capture: Option<Capture>,
+    captured: Option<Vec<String>>,
+
+    /// Action name -> Rust handler called when the action fires
+    handlers: HashMap<String, ActionHandler>,
+
+    paused: bool,
+    /// Still emitted while paused, and resumes the listener when it fires
+    resume_action: Option<String>,
 
     callback_sender: Sender<KeyCode>,
     callback_receiver: Receiver<KeyCode>,
 
-    listener_sender: Sender<String>,
+    listener_sender: Sender<ActionEvent>,
 }
 
 impl HotkeyListener {
     /// Creates a new instance of `HotkeyListener`. This operation _can_ fail.
-    pub fn new(listener_sender: Sender<String>) -> Result<Self> {
-        let hook = match Hook::new() {
-            Ok(h) => h,
-            Err(e) => {
-                eprintln!("{e}");
-                return Err(Error::HookCreate);
+    ///
+    /// Creating the OS hook is retried a few times with backoff since it can fail if the desktop session
+    /// is not fully ready yet, e.g. when launched at startup.
+    pub fn new(listener_sender: Sender<ActionEvent>) -> Result<Self> {
+        let mut backoff = HOOK_CREATE_BACKOFF;
+        let mut attempt = 1;
+        let hook = loop {
+            match Hook::new() {
+                Ok(h) => break h,
+                Err(e) => {
+                    eprintln!("{e}");
+                    if attempt >= HOOK_CREATE_ATTEMPTS {
+                        return Err(Error::HookCreate);
+                    }
+                }
             }
+
+            std::thread::sleep(backoff);
+            backoff *= 2;
+            attempt += 1;
         };
 
         let (sender, receiver) = unbounded::<KeyCode>();
@@ -133,7 +552,39 @@ impl HotkeyListener {
             actions: HashMap::new(),
             reverse_lookup: HashMap::new(),
 
-            min_elapsed_time: Duration::from_secs_f32(0.2), // TODO hardcoded value?
+            key_groups: HashMap::new(),
+
+            timing: TimingConfig::default(),
+
+            history: VecDeque::with_capacity(DEFAULT_HISTORY_CAPACITY),
+            history_capacity: DEFAULT_HISTORY_CAPACITY,
+            trigger_counts: HashMap::new(),
+
+            action_output_debounce: HashMap::new(),
+            last_emitted: HashMap::new(),
+
+            actions_with_effects: HashSet::new(),
+            warned_no_effect: HashSet::new(),
+
+            exclusive_groups: HashMap::new(),
+            active_exclusive: HashMap::new(),
+            focus_filters: HashMap::new(),
+            descriptions: HashMap::new(),
+
+            last_health_check: Instant::now(),
+            hook_health_threshold: None,
+            hook_healthy: true,
+
+            passthrough: None,
+            passthrough_keys: HashSet::new(),
+
+            capture: None,
+            captured: None,
+
+            handlers: HashMap::new(),
+
+            paused: false,
+            resume_action: None,
 
             callback_sender: sender,
             callback_receiver: receiver,
@@ -142,28 +593,213 @@ impl HotkeyListener {
         })
     }
 
+    /// Defines a logical key name that is satisfied by any one of the given physical keys, e.g.
+    /// `AnyEnter` for either `Enter` or `NumpadEnter`. Groups take precedence over the built-in generic
+    /// modifiers like `Shift`.
+    ///
+    /// Only affects actions registered after the group is defined.
+    pub fn set_key_group(&mut self, group_name: &str, keys: &[String]) -> Result<()> {
+        if keys.is_empty() {
+            return Err(Error::EmptyKeyGroup(group_name.to_string()));
+        }
+
+        let mut key_codes = vec![];
+        for key in keys.iter() {
+            key_codes.push(parse_key(key)?);
+        }
+
+        self.key_groups.insert(group_name.to_string(), key_codes);
+
+        Ok(())
+    }
+
+    /// Defines a custom name for a key, e.g. `MyMacroKey` for `F13`. Aliases take precedence over
+    /// built-in key names.
+    ///
+    /// Only affects actions registered after the alias is defined.
+    pub fn set_key_alias(&mut self, alias: &str, key: &str) -> Result<()> {
+        self.set_key_group(alias, &[key.to_string()])
+    }
+
     /// Registers an action by name and key sequence. The key sequence is hashed and that hash is used to store
     /// action names.
     ///
     /// For every key associated with the action, a reverse lookup is used (key -> action) for quick access.
+    ///
+    /// Actions may share keys. Every action whose keys are pressed fires, so registering `A` on
+    /// `ControlLeft + KeyA` and `B` on `KeyA` emits both when `ControlLeft + KeyA` is pressed.
     pub fn register_action(&mut self, action_name: &String, keys: &[String]) -> Result<()> {
-        let (key_codes, key_codes_hash) = match string_slice_to_vec_and_hash(keys) {
-            Ok(v) => v,
-            Err(e) => return Err(e),
+        self.register_action_from(action_name, keys, BindingSource::Config)
+    }
+
+    /// Same as `register_action` but records where the binding came from. See `get_bindings_by_source`.
+    pub fn register_action_from(
+        &mut self,
+        action_name: &String,
+        keys: &[String],
+        source: BindingSource,
+    ) -> Result<()> {
+        self.register(action_name, keys, source, MappingOptions::default())
+    }
+
+    /// Same as `register_action` but the keys must be pressed within `window` of each other instead of the
+    /// listener's minimum elapsed time.
+    ///
+    /// The window belongs to the key sequence, so it also applies to other actions registered to the same keys.
+    pub fn register_action_with_window(
+        &mut self,
+        action_name: &String,
+        keys: &[String],
+        window: Duration,
+    ) -> Result<()> {
+        let options = MappingOptions {
+            window: Some(window),
+            ..Default::default()
+        };
+        self.register(action_name, keys, BindingSource::Config, options)
+    }
+
+    /// Same as `register_action` but the key sequence cannot fire again until `cooldown` has passed since it
+    /// last fired, e.g. to stop a held chord from firing on every extra key press.
+    ///
+    /// The cooldown belongs to the key sequence, so it also applies to other actions registered to the same keys.
+    pub fn register_action_with_cooldown(
+        &mut self,
+        action_name: &String,
+        keys: &[String],
+        cooldown: Duration,
+    ) -> Result<()> {
+        let options = MappingOptions {
+            cooldown: Some(cooldown),
+            ..Default::default()
+        };
+        self.register(action_name, keys, BindingSource::Config, options)
+    }
+
+    /// Registers an action to several key sequences at once, firing on any of them. If one of the key sequences
+    /// cannot be registered, the ones registered before it are unregistered again.
+    ///
+    /// Use `unregister_action_all` to remove every key sequence at once.
+    pub fn register_action_aliases(
+        &mut self,
+        action_name: &String,
+        combos: &[Vec<String>],
+    ) -> Result<()> {
+        for (i, keys) in combos.iter().enumerate() {
+            if let Err(e) = self.register_action(action_name, keys) {
+                for registered in combos.iter().take(i) {
+                    if let Err(e) = self.unregister_action(action_name, registered) {
+                        eprintln!("{e}");
+                    }
+                }
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Registers an action that only fires if the keys are pressed in the given order, e.g. `ControlLeft`
+    /// then `KeyK` then `KeyS`. The timing rules are the same as for `register_action`.
+    ///
+    /// A sequence is a separate binding from an unordered action with the same keys.
+    pub fn register_sequence(&mut self, action_name: &String, keys: &[String]) -> Result<()> {
+        let options = MappingOptions {
+            ordered: true,
+            ..Default::default()
+        };
+        self.register(action_name, keys, BindingSource::Config, options)
+    }
+
+    /// Registers an action that fires when a single key is pressed `taps` times within `within`, e.g.
+    /// `ScrollLock` twice within 300ms. The count starts over after the action fires. A `taps` of `0` is
+    /// treated as `1`.
+    ///
+    /// A tap action is a separate binding from other actions on the same key, so those still fire on every press.
+    pub fn register_tap_action(
+        &mut self,
+        action_name: &String,
+        key: &String,
+        taps: u32,
+        within: Duration,
+    ) -> Result<()> {
+        let options = MappingOptions {
+            taps: Some((taps.max(1), within)),
+            ..Default::default()
         };
+        self.register(
+            action_name,
+            std::slice::from_ref(key),
+            BindingSource::Config,
+            options,
+        )
+    }
+
+    fn register(
+        &mut self,
+        action_name: &String,
+        keys: &[String],
+        source: BindingSource,
+        options: MappingOptions,
+    ) -> Result<()> {
+        let (key_slots, key_codes_hash) = self.slots_and_hash(keys, &options)?;
+        let key_codes = unique_key_codes(&key_slots);
+
+        if let Some(am) = self.actions.get(&key_codes_hash) {
+            if am.actions.contains(action_name) {
+                return Err(Error::ActionAlreadyExists);
+            }
+        }
+
+        // Hook new keys before touching anything else so a failure can't leave the maps half updated
+        let mut hooked_keys: Vec<KeyCode> = vec![];
+        for key in key_codes.iter() {
+            if self.reverse_lookup.contains_key(key) || self.passthrough_keys.contains(key) {
+                continue;
+            }
+
+            if let Err(e) = register_hook(&self.hook, *key, &self.callback_sender) {
+                for hooked_key in hooked_keys.iter() {
+                    if let Err(e) = self.hook.unregister(*hooked_key) {
+                        eprintln!("{e}");
+                    }
+                }
+                return Err(e);
+            }
+            hooked_keys.push(*key);
+        }
 
         match self.actions.get_mut(&key_codes_hash) {
-            Some(am) => match am.add_action(action_name) {
+            Some(am) => match am.add_action(action_name, source) {
                 Ok(_) => {}
                 Err(e) => return Err(e),
             },
             None => {
-                let mut am = ActionMapping::new(key_codes.as_slice());
-                am.add_action(action_name).unwrap();
+                let mut key_names = keys.to_vec();
+                if options.ordered {
+                    let mut seen = HashSet::new();
+                    key_names.retain(|k| seen.insert(k.clone()));
+                } else {
+                    key_names.sort();
+                    key_names.dedup();
+                }
+
+                let mut am =
+                    ActionMapping::new(key_slots.as_slice(), key_names.as_slice(), &options);
+                am.add_action(action_name, source)?;
                 self.actions.insert(key_codes_hash, am);
             }
         }
 
+        if let Some(am) = self.actions.get_mut(&key_codes_hash) {
+            if options.window.is_some() {
+                am.window = options.window;
+            }
+            if let Some(cooldown) = options.cooldown {
+                am.cooldown = cooldown;
+            }
+        }
+
         for key in key_codes.iter() {
             match self.reverse_lookup.get_mut(key) {
                 Some(v) => {
@@ -172,32 +808,62 @@ impl HotkeyListener {
                     }
                 }
                 None => {
-                    let sender = self.callback_sender.clone();
-                    let key = key.clone();
-                    match self.hook.register(key, move || match sender.send(key) {
-                        Ok(_) => {}
-                        Err(e) => eprintln!("{e}"),
-                    }) {
-                        Ok(_) => {}
-                        Err(e) => {
-                            return Err(Error::CannotRegisterHotkey(e));
-                        }
-                    }
-                    self.reverse_lookup.insert(key, vec![key_codes_hash]);
+                    self.reverse_lookup.insert(*key, vec![key_codes_hash]);
                 }
             }
+
+            // The hook is now owned by the reverse lookup
+            self.passthrough_keys.remove(key);
         }
 
-        Ok(())
+        if let Some(reserved) = reserved_chord_for(&key_slots) {
+            log::warn!(
+                "Action {} uses {}, which is an OS shortcut and may not work as expected",
+                action_name,
+                reserved.join(" + ")
+            );
+        }
+
+        self.check_consistency()
     }
 
     /// Safely removes an action + key sequence without accidentally removing other action's hotkeys.
     /// If no more actions depend on a certain key, the hook for that key is unregistered.
     pub fn unregister_action(&mut self, action_name: &String, keys: &[String]) -> Result<()> {
-        let (key_codes, key_codes_hash) = match string_slice_to_vec_and_hash(keys) {
-            Ok(v) => v,
-            Err(e) => return Err(e),
+        self.unregister(action_name, keys, &MappingOptions::default())
+    }
+
+    /// Removes an action registered with `register_sequence`. The keys must be in the same order.
+    pub fn unregister_sequence(&mut self, action_name: &String, keys: &[String]) -> Result<()> {
+        let options = MappingOptions {
+            ordered: true,
+            ..Default::default()
+        };
+        self.unregister(action_name, keys, &options)
+    }
+
+    /// Removes an action registered with `register_tap_action`. The taps and time must be the same.
+    pub fn unregister_tap_action(
+        &mut self,
+        action_name: &String,
+        key: &String,
+        taps: u32,
+        within: Duration,
+    ) -> Result<()> {
+        let options = MappingOptions {
+            taps: Some((taps.max(1), within)),
+            ..Default::default()
         };
+        self.unregister(action_name, std::slice::from_ref(key), &options)
+    }
+
+    fn unregister(
+        &mut self,
+        action_name: &String,
+        keys: &[String],
+        options: &MappingOptions,
+    ) -> Result<()> {
+        let (_, key_codes_hash) = self.slots_and_hash(keys, options)?;
 
         let mut is_empty_hash = false;
 
@@ -214,110 +880,1130 @@ impl HotkeyListener {
         }
 
         if !is_empty_hash {
-            return Ok(());
+            return self.check_consistency();
         }
 
-        let mut empty_keys: Vec<KeyCode> = vec![];
+        self.remove_chord(key_codes_hash)
+    }
 
-        match self.actions.remove(&key_codes_hash) {
-            Some(_) => {}
-            None => unreachable!(),
-        }
+    /// Stops emitting an action without unregistering it, so its keys stay hooked and `enable_action`
+    /// is instant.
+    pub fn disable_action(&mut self, action_name: &String, keys: &[String]) -> Result<()> {
+        self.set_action_enabled(action_name, keys, false)
+    }
 
-        for key in key_codes.iter() {
-            match self.reverse_lookup.get_mut(key) {
-                Some(v) => {
-                    v.retain(|hash| hash != &key_codes_hash);
-                    if v.is_empty() {
-                        empty_keys.push(*key);
-                    }
-                }
-                None => unreachable!(),
-            }
+    /// Resumes emitting an action that was disabled with `disable_action`.
+    pub fn enable_action(&mut self, action_name: &String, keys: &[String]) -> Result<()> {
+        self.set_action_enabled(action_name, keys, true)
+    }
+
+    fn set_action_enabled(
+        &mut self,
+        action_name: &String,
+        keys: &[String],
+        enabled: bool,
+    ) -> Result<()> {
+        let (_, key_codes_hash) = self.slots_and_hash(keys, &MappingOptions::default())?;
+
+        let am = match self.actions.get_mut(&key_codes_hash) {
+            Some(am) => am,
+            None => return Err(Error::ActionDoesNotExist(MapType::Actions)),
+        };
+        if !am.actions.contains(action_name) {
+            return Err(Error::ActionDoesNotExist(MapType::ActionMapping));
         }
 
-        for key in empty_keys.iter() {
-            match self.reverse_lookup.remove(key) {
-                Some(_) => match self.hook.unregister(*key) {
-                    Ok(_) => {}
-                    Err(e) => return Err(Error::CannotUnregisterHotkey(e)),
-                },
-                None => unreachable!(),
-            }
+        if enabled {
+            am.disabled.remove(action_name);
+        } else {
+            am.disabled.insert(action_name.clone());
         }
 
         Ok(())
     }
 
-    // TODO maybe we should clear the channel? Clearing the channel might infinitely loop though
-    /// Checks if any actions have been triggered. Needs to be polled at regular intervals
-    /// or else the receivers might grow infinitely large or the senders might block infinitely.
-    pub fn poll(&mut self) {
-        if self.callback_receiver.is_empty() {
-            return;
+    /// Resolves key names to key slots and hashes them together with anything in `options` that makes the
+    /// key sequence a separate binding.
+    fn slots_and_hash(
+        &self,
+        keys: &[String],
+        options: &MappingOptions,
+    ) -> Result<(Vec<Vec<KeyCode>>, u64)> {
+        let (key_slots, key_codes_hash) =
+            string_slice_to_vec_and_hash(keys, &self.key_groups, options.ordered)?;
+
+        match options.taps {
+            Some(taps) => Ok((key_slots, get_hash(&(key_codes_hash, taps)))),
+            None => Ok((key_slots, key_codes_hash)),
         }
+    }
 
-        match self.callback_receiver.recv() {
-            Ok(key) => {
-                if !self.reverse_lookup.contains_key(&key) {
-                    return;
-                }
+    /// Removes an action from every key sequence it is registered to, no matter how it was registered.
+    pub fn unregister_action_all(&mut self, action_name: &String) -> Result<()> {
+        let hashes = self
+            .actions
+            .iter()
+            .filter(|(_, am)| am.actions.contains(action_name))
+            .map(|(hash, _)| *hash)
+            .collect::<Vec<u64>>();
+        if hashes.is_empty() {
+            return Err(Error::ActionDoesNotExist(MapType::Actions));
+        }
 
-                let vec = match self.reverse_lookup.get(&key) {
-                    Some(v) => v,
-                    None => {
-                        return;
-                    }
-                };
+        let mut result = Ok(());
+        for hash in hashes {
+            let is_empty_hash = match self.actions.get_mut(&hash) {
+                Some(am) => {
+                    am.remove_action(action_name)?;
+                    am.actions.is_empty()
+                }
+                None => continue,
+            };
 
-                for hash in vec.iter() {
-                    match self.actions.get_mut(&hash) {
-                        Some(am) => {
-                            am.press_key(&key);
-                            if am.is_pressed(&self.min_elapsed_time) {
-                                for action_name in am.actions.iter() {
-                                    match self.listener_sender.send(action_name.clone()) {
-                                        Ok(_) => {}
-                                        Err(e) => eprintln!("{e}"),
-                                    }
-                                }
-                            }
-                        }
-                        None => unreachable!(),
-                    }
+            if is_empty_hash {
+                if let Err(e) = self.remove_chord(hash) {
+                    result = Err(e);
                 }
             }
-            Err(e) => eprintln!("{e}"),
         }
-    }
-
-    /// Returns the minimum elapsed time as an `f32` in seconds.
-    pub fn get_min_elapsed_time(&self) -> f32 {
-        self.min_elapsed_time.as_secs_f32()
-    }
 
-    /// Converts an `f32` into a `Duration`. Treats the `f32` as seconds.
-    pub fn set_min_elapsed_time(&mut self, min_elapsed_time: f32) {
-        self.min_elapsed_time = Duration::from_secs_f32(min_elapsed_time);
+        result.and(self.check_consistency())
     }
 
-    /// Iterates through all actions and returns a non-repeating `Vec` of all registered actions.
+    /// Renames an action everywhere it is registered. Its keys stay hooked and its settings, like handlers,
+    /// exclusive groups, and debounces, move over to the new name.
     ///
-    /// The `Vec` is initially unsorted but is sorted in order to remove duplicates.
-    pub fn get_action_names(&self) -> Vec<String> {
-        let mut r = self
+    /// Errors with `ActionAlreadyExists` if any key sequence of `old` already emits `new`, in which case
+    /// nothing is renamed.
+    pub fn rename_action(&mut self, old: &str, new: &str) -> Result<()> {
+        let hashes = self
             .actions
-            .values()
-            .into_iter()
-            .flat_map(|am| am.actions.clone())
-            .collect::<Vec<String>>();
+            .iter()
+            .filter(|(_, am)| am.actions.iter().any(|a| a == old))
+            .map(|(hash, _)| *hash)
+            .collect::<Vec<u64>>();
+        if hashes.is_empty() {
+            return Err(Error::ActionDoesNotExist(MapType::Actions));
+        }
+        if old == new {
+            return Ok(());
+        }
+        if hashes
+            .iter()
+            .any(|hash| self.actions[hash].actions.iter().any(|a| a == new))
+        {
+            return Err(Error::ActionAlreadyExists);
+        }
 
-        r.sort_unstable();
-        r.dedup();
+        for hash in hashes {
+            if let Some(am) = self.actions.get_mut(&hash) {
+                am.rename_action(old, new);
+            }
+        }
+
+        rename_key(&mut self.trigger_counts, old, new);
+        rename_key(&mut self.action_output_debounce, old, new);
+        rename_key(&mut self.last_emitted, old, new);
+        rename_key(&mut self.exclusive_groups, old, new);
+        rename_key(&mut self.focus_filters, old, new);
+        rename_key(&mut self.descriptions, old, new);
+        rename_key(&mut self.handlers, old, new);
+        for (active, _) in self.active_exclusive.values_mut() {
+            if active == old {
+                *active = new.to_string();
+            }
+        }
+        if self.actions_with_effects.remove(old) {
+            self.actions_with_effects.insert(new.to_string());
+        }
+        if self.resume_action.as_deref() == Some(old) {
+            self.resume_action = Some(new.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Removes every key sequence and releases their hooks, leaving the listener ready for fresh registration.
+    /// The active members of exclusive groups are forgotten as well.
+    ///
+    /// Settings that are not tied to a key sequence, like key groups, handlers, and per-action debounces,
+    /// are kept, so actions registered again behave the same.
+    pub fn clear(&mut self) -> Result<()> {
+        self.actions.clear();
+        self.active_exclusive.clear();
+        let keys = self
+            .reverse_lookup
+            .drain()
+            .map(|(key, _)| key)
+            .collect::<Vec<KeyCode>>();
+
+        // Keep going on failure so every hook gets a chance to be released
+        let mut result = Ok(());
+        for key in keys {
+            // Still needed to pass keys through or capture them
+            if self.needs_every_key() {
+                self.passthrough_keys.insert(key);
+                continue;
+            }
+
+            if let Err(e) = self.hook.unregister(key) {
+                result = Err(Error::CannotUnregisterHotkey(e));
+            }
+        }
+
+        result
+    }
+
+    /// Removes a key sequence and every action associated with it, identified by the hash of the
+    /// key sequence. This is the `id` returned by `get_action_bindings`.
+    pub fn unregister_chord(&mut self, hash: u64) -> Result<()> {
+        if !self.actions.contains_key(&hash) {
+            return Err(Error::ActionDoesNotExist(MapType::Actions));
+        }
+
+        self.remove_chord(hash)
+    }
+
+    /// Removes a key sequence from the actions and reverse lookup.
+    /// If no more actions depend on a certain key, the hook for that key is unregistered.
+    fn remove_chord(&mut self, key_codes_hash: u64) -> Result<()> {
+        let mut empty_keys: Vec<KeyCode> = vec![];
+
+        let key_codes = match self.actions.remove(&key_codes_hash) {
+            Some(am) => am.keys.into_keys().collect::<Vec<KeyCode>>(),
+            None => return Err(Error::ActionDoesNotExist(MapType::Actions)),
+        };
+
+        // Keep going on failure so the maps stay in sync, a dangling hook is only wasted work
+        let mut result = Ok(());
+
+        for key in key_codes.iter() {
+            match self.reverse_lookup.get_mut(key) {
+                Some(v) => {
+                    v.retain(|hash| hash != &key_codes_hash);
+                    if v.is_empty() {
+                        empty_keys.push(*key);
+                    }
+                }
+                None => result = Err(Error::MappedKeyMissingInReverseLookup),
+            }
+        }
+
+        for key in empty_keys.iter() {
+            match self.reverse_lookup.remove(key) {
+                // Still needed to pass keys through or capture them
+                Some(_) if self.needs_every_key() => {
+                    self.passthrough_keys.insert(*key);
+                }
+                Some(_) => match self.hook.unregister(*key) {
+                    Ok(_) => {}
+                    Err(e) => result = Err(Error::CannotUnregisterHotkey(e)),
+                },
+                None => result = Err(Error::KeyNotMapped),
+            }
+        }
+
+        result.and(self.check_consistency())
+    }
+
+    /// Verifies that the actions and the reverse lookup agree with each other. Called after every
+    /// operation that mutates them so that torn state is reported as an error instead of panicking in `poll`.
+    fn check_consistency(&self) -> Result<()> {
+        for (hash, am) in self.actions.iter() {
+            for key in am.keys.keys() {
+                match self.reverse_lookup.get(key) {
+                    Some(v) if v.contains(hash) => {}
+                    _ => return Err(Error::MappedKeyMissingInReverseLookup),
+                }
+            }
+        }
+
+        for hashes in self.reverse_lookup.values() {
+            if hashes.iter().any(|hash| !self.actions.contains_key(hash)) {
+                return Err(Error::ActionDoesNotExist(MapType::ReverseLookup));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks if any actions have been triggered. Needs to be polled at regular intervals
+    /// or else the receivers might grow infinitely large or the senders might block infinitely.
+    ///
+    /// Every key that was queued when `poll` was called is processed, so actions never lag behind input no matter
+    /// how rarely `poll` is called. Keys queued while polling are left for the next call so a flooding sender
+    /// cannot keep `poll` from returning. Consecutive presses of the same key, e.g. from OS key repeat,
+    /// are collapsed into a single press, unless the key is used by a tap action since a double tap
+    /// polled at once looks the same.
+    ///
+    /// Returns the names of the actions emitted by this call, in order. Errors with `ListenerChannelClosed` if
+    /// the receiving end of the `listener_sender` was dropped, since nothing can observe the listener anymore.
+    pub fn poll(&mut self) -> Result<Vec<String>> {
+        let pressed = self
+            .callback_receiver
+            .try_iter()
+            .take(self.callback_receiver.len())
+            .collect::<Vec<KeyCode>>();
+
+        if let Some(passthrough) = self.passthrough.as_ref() {
+            if pressed.iter().any(|key| passthrough.send(*key).is_err()) {
+                eprintln!("Passthrough receiver disconnected, disabling passthrough");
+                self.set_passthrough(None);
+            }
+        }
+
+        let mut keys: Vec<KeyCode> = vec![];
+        for key in pressed {
+            if keys.last() != Some(&key) || self.has_tap_action(key) {
+                keys.push(key);
+            }
+        }
+
+        self.check_hook_health();
+
+        if self.capture.is_some() {
+            self.capture_keys(&keys);
+            return Ok(vec![]);
+        }
+
+        let mut emitted = vec![];
+        for key in keys {
+            emitted.extend(self.process_key(key)?);
+        }
+
+        Ok(emitted)
+    }
+
+    /// Whether any tap action is bound to the key.
+    fn has_tap_action(&self, key: KeyCode) -> bool {
+        self.reverse_lookup.get(&key).is_some_and(|hashes| {
+            hashes
+                .iter()
+                .any(|hash| self.actions.get(hash).is_some_and(|am| am.taps.is_some()))
+        })
+    }
+
+    /// Moves the listener onto its own thread that calls `poll` every `tick`. Emitted actions are sent out
+    /// through the `listener_sender` as usual.
+    ///
+    /// The thread runs until `ListenerHandle::stop` is called or polling fails, e.g. because the receiving
+    /// end of the `listener_sender` was dropped. Manual polling with `poll` is still available for callers
+    /// that drive their own loop.
+    pub fn spawn(mut self, tick: Duration) -> ListenerHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+
+        let join_handle = thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                self.poll()?;
+                thread::sleep(tick);
+            }
+
+            Ok(self)
+        });
+
+        ListenerHandle { stop, join_handle }
+    }
+
+    /// Probes the hook once per hook health threshold and recreates it if it stopped working, since some
+    /// platforms silently drop it, e.g. after an RDP reconnect.
+    ///
+    /// Not receiving any keys is not a sign of a dead hook, since the user may just be idle, so the hook itself
+    /// is asked instead. See `hook_responds`.
+    fn check_hook_health(&mut self) {
+        let threshold = match self.hook_health_threshold {
+            Some(t) => t,
+            None => return,
+        };
+
+        if self.reverse_lookup.is_empty() || self.last_health_check.elapsed() < threshold {
+            return;
+        }
+        self.last_health_check = Instant::now();
+
+        if self.hook_responds() {
+            self.hook_healthy = true;
+            return;
+        }
+        self.hook_healthy = false;
+
+        eprintln!("Hotkey hook stopped responding, recreating it");
+
+        let hook = match Hook::new() {
+            Ok(h) => h,
+            Err(e) => {
+                eprintln!("{e}");
+                return;
+            }
+        };
+
+        for key in self
+            .reverse_lookup
+            .keys()
+            .chain(self.passthrough_keys.iter())
+        {
+            if let Err(e) = register_hook(&hook, *key, &self.callback_sender) {
+                eprintln!("{e}");
+                return;
+            }
+        }
+
+        self.hook = hook;
+        self.hook_healthy = true;
+
+        eprintln!("Recreated hook with {} keys", self.reverse_lookup.len());
+    }
+
+    /// Asks the hook to unregister a key that is not hooked. A working hook answers that the key is not
+    /// registered, while a hook whose thread died cannot answer at all.
+    fn hook_responds(&self) -> bool {
+        let probe = KEY_NAMES
+            .iter()
+            .filter_map(|name| KeyCode::from_str(name).ok())
+            .find(|key| {
+                !self.reverse_lookup.contains_key(key) && !self.passthrough_keys.contains(key)
+            });
+
+        match probe {
+            Some(key) => matches!(
+                self.hook.unregister(key),
+                Ok(_) | Err(livesplit_hotkey::Error::NotRegistered)
+            ),
+            // Every key is hooked, so there is nothing to probe with
+            None => true,
+        }
+    }
+
+    /// Forwards every pressed key to `sender`, in addition to the normal action processing.
+    /// Every key is hooked while passthrough is enabled. `None` disables passthrough.
+    ///
+    /// Keys are forwarded as they are polled. Every press is forwarded, including the repeats `poll` collapses.
+    pub fn set_passthrough(&mut self, sender: Option<Sender<KeyCode>>) {
+        match sender {
+            Some(sender) => {
+                self.hook_every_key();
+                self.passthrough = Some(sender);
+            }
+            None => {
+                self.passthrough = None;
+                self.unhook_extra_keys();
+            }
+        }
+    }
+
+    /// Starts recording the next keys pressed within the minimum elapsed time of each other, e.g. for a
+    /// settings screen that asks the user to press the chord they want. Actions are not emitted while capturing,
+    /// and every key is hooked.
+    ///
+    /// Capturing ends once the minimum elapsed time has passed since the first key, or on `stop_capture`.
+    /// The result is retrieved with `take_capture`.
+    pub fn start_capture(&mut self) {
+        self.captured = None;
+        self.capture = Some(Capture {
+            keys: vec![],
+            first: None,
+        });
+        self.hook_every_key();
+    }
+
+    /// Ends capturing early, keeping any keys captured so far.
+    pub fn stop_capture(&mut self) {
+        if let Some(capture) = self.capture.take() {
+            self.finish_capture(capture);
+        }
+    }
+
+    /// Whether keys are still being captured.
+    pub fn is_capturing(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    /// Takes the canonical names of the captured keys, in the order they were pressed. `None` if capturing
+    /// has not finished or no keys were pressed.
+    pub fn take_capture(&mut self) -> Option<Vec<String>> {
+        self.captured.take()
+    }
+
+    /// Adds polled keys to the capture and ends it once the minimum elapsed time has passed since the first key.
+    fn capture_keys(&mut self, keys: &[KeyCode]) {
+        let mut capture = match self.capture.take() {
+            Some(c) => c,
+            None => return,
+        };
+
+        let now = Instant::now();
+        for key in keys.iter() {
+            let first = *capture.first.get_or_insert(now);
+            if now.duration_since(first) > self.timing.min_elapsed_time {
+                break;
+            }
+
+            if !capture.keys.contains(key) {
+                capture.keys.push(*key);
+            }
+        }
+
+        match capture.first {
+            Some(first) if first.elapsed() > self.timing.min_elapsed_time => {
+                self.finish_capture(capture)
+            }
+            _ => self.capture = Some(capture),
+        }
+    }
+
+    fn finish_capture(&mut self, capture: Capture) {
+        if !capture.keys.is_empty() {
+            self.captured = Some(
+                capture
+                    .keys
+                    .iter()
+                    .map(|key| key_name(*key).to_string())
+                    .collect(),
+            );
+        }
+
+        self.unhook_extra_keys();
+    }
+
+    /// Whether keys that are not part of any action still need to be hooked.
+    fn needs_every_key(&self) -> bool {
+        self.passthrough.is_some() || self.capture.is_some()
+    }
+
+    /// Hooks every key that is not already hooked.
+    fn hook_every_key(&mut self) {
+        for name in KEY_NAMES.iter() {
+            let key = match KeyCode::from_str(name) {
+                Ok(k) => k,
+                Err(_) => continue,
+            };
+            if self.reverse_lookup.contains_key(&key) || self.passthrough_keys.contains(&key) {
+                continue;
+            }
+
+            // Not every key can be hooked on every platform
+            if register_hook(&self.hook, key, &self.callback_sender).is_ok() {
+                self.passthrough_keys.insert(key);
+            }
+        }
+    }
+
+    /// Unhooks every key that is not part of an action, unless passthrough or capturing still needs them.
+    fn unhook_extra_keys(&mut self) {
+        if self.needs_every_key() {
+            return;
+        }
+
+        for key in self.passthrough_keys.drain() {
+            if let Err(e) = self.hook.unregister(key) {
+                eprintln!("{e}");
+            }
+        }
+    }
+
+    /// Stops emitting actions until `resume` is called. Keys are still drained by `poll` and every
+    /// binding stays registered, so resuming is instant.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Starts emitting actions again after `pause`.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Whether actions are being suppressed by `pause`.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Sets an action that is still emitted while paused. Firing it also resumes the listener, so it can be
+    /// used as a resume hotkey. `None` removes it.
+    pub fn set_resume_action(&mut self, action_name: Option<&str>) {
+        self.resume_action = action_name.map(|a| a.to_string());
+    }
+
+    /// Whether the hook was working the last time it was checked, or was recreated successfully.
+    /// Always `true` unless a hook health threshold is set.
+    pub fn hook_healthy(&self) -> bool {
+        self.hook_healthy
+    }
+
+    /// Sets how often the hook is checked, and recreated if it stopped working. `None` disables the check.
+    pub fn set_hook_health_threshold(&mut self, threshold: Option<Duration>) {
+        self.hook_health_threshold = threshold;
+        self.last_health_check = Instant::now();
+        self.hook_healthy = true;
+    }
+
+    /// Presses a key for every action mapping that uses it and emits any actions that are now pressed.
+    ///
+    /// Returns the names of the emitted actions.
+    fn process_key(&mut self, key: KeyCode) -> Result<Vec<String>> {
+        let vec = match self.reverse_lookup.get(&key) {
+            Some(v) => v,
+            None => {
+                return Ok(vec![]);
+            }
+        };
+
+        // An out of sync mapping is skipped instead of taking the whole listener down
+        let mut fired = vec![];
+        for hash in vec.iter() {
+            match self.actions.get_mut(hash) {
+                // Left untouched while paused, so presses made while paused cannot count towards taps or
+                // cooldowns once resumed
+                Some(am)
+                    if self.paused
+                        && !self
+                            .resume_action
+                            .as_ref()
+                            .is_some_and(|resume| am.actions.contains(resume)) => {}
+                Some(am) => {
+                    if let Err(e) = am.press_key(&key) {
+                        eprintln!("{e}");
+                        continue;
+                    }
+                    if am.is_pressed(
+                        &self.timing.min_elapsed_time,
+                        self.timing.sequential_grace.as_ref(),
+                    ) && am.try_fire(Instant::now())
+                    {
+                        for action_name in am.actions.iter() {
+                            if am.disabled.contains(action_name) {
+                                continue;
+                            }
+                            fired.push((action_name.clone(), am.key_names.clone()));
+                        }
+                        am.reset_taps();
+                    }
+                }
+                None => eprintln!("{}", Error::ActionDoesNotExist(MapType::Actions)),
+            }
+        }
+
+        // Only look up the focused window if something needs it, since it can be slow
+        let focused = if fired
+            .iter()
+            .any(|(action_name, _)| self.focus_filters.contains_key(action_name))
+        {
+            crate::focus::active_window_name()
+        } else {
+            None
+        };
+
+        let mut emitted = vec![];
+        for (action_name, keys) in fired {
+            if let Some(filter) = self.focus_filters.get(&action_name) {
+                let focus_matches = match focused.as_ref() {
+                    Some(name) => crate::focus::matches(filter, name),
+                    None => false,
+                };
+                if !focus_matches {
+                    continue;
+                }
+            }
+
+            if self.paused {
+                if self.resume_action.as_ref() != Some(&action_name) {
+                    continue;
+                }
+                self.resume();
+            }
+
+            if self.emit_action(action_name.clone(), keys)? {
+                emitted.push(action_name);
+            }
+        }
+
+        Ok(emitted)
+    }
+
+    /// Sends a fired action to the `listener_sender`, calls its handler, and records it in the action history.
+    ///
+    /// If the action was already emitted within its output debounce, nothing is sent.
+    /// If the action is part of an exclusive group, the previously active member of that group
+    /// is turned off first, using the keys that activated it.
+    ///
+    /// Returns whether the action was emitted.
+    fn emit_action(&mut self, action_name: String, keys: Vec<String>) -> Result<bool> {
+        let debounce = self
+            .action_output_debounce
+            .get(&action_name)
+            .unwrap_or(&self.timing.output_debounce);
+        if let Some(last) = self.last_emitted.get(&action_name) {
+            if last.elapsed() < *debounce {
+                return Ok(false);
+            }
+        }
+        self.last_emitted
+            .insert(action_name.clone(), Instant::now());
+
+        if !self.actions_with_effects.contains(&action_name)
+            && self.warned_no_effect.insert(action_name.clone())
+        {
+            log::warn!("Action {} fired but has nothing handling it", action_name);
+        }
+
+        if let Some(group) = self.exclusive_groups.get(&action_name) {
+            match self
+                .active_exclusive
+                .insert(group.clone(), (action_name.clone(), keys.clone()))
+            {
+                Some((previous, previous_keys)) if previous != action_name => {
+                    self.record_event(format!("{previous}{OFF_SUFFIX}"), previous_keys)?;
+                }
+                _ => {}
+            }
+        }
+
+        self.record_event(action_name, keys)?;
+
+        Ok(true)
+    }
+
+    /// Sends an event to the `listener_sender`, counts it, calls its handler, and adds it to the action history.
+    fn record_event(&mut self, name: String, keys: Vec<String>) -> Result<()> {
+        let event = ActionEvent::new(name, keys);
+
+        if self.listener_sender.send(event.clone()).is_err() {
+            return Err(Error::ListenerChannelClosed);
+        }
+
+        *self.trigger_counts.entry(event.name.clone()).or_default() += 1;
+
+        if let Some(handler) = self.handlers.get_mut(&event.name) {
+            handler(&event);
+        }
+
+        if self.history_capacity > 0 {
+            if self.history.len() >= self.history_capacity {
+                self.history.pop_front();
+            }
+            self.history.push_back(event);
+        }
+
+        Ok(())
+    }
+
+    /// Registers a Rust handler that is called from `poll` whenever the action fires. This replaces any
+    /// existing handler for the action. The action is also marked as having an effect.
+    ///
+    /// Handlers run in addition to the action being sent to the `listener_sender`.
+    pub fn on(&mut self, action_name: &str, handler: impl FnMut(&ActionEvent) + Send + 'static) {
+        self.handlers
+            .insert(action_name.to_string(), Box::new(handler));
+        self.mark_has_effect(action_name);
+    }
+
+    /// Removes the Rust handler for an action, returning whether one existed.
+    pub fn off(&mut self, action_name: &str) -> bool {
+        self.handlers.remove(action_name).is_some()
+    }
+
+    /// Marks an action as having something that handles it, e.g. a script handler or a sink.
+    /// Actions that fire without being marked log a warning the first time they fire.
+    pub fn mark_has_effect(&mut self, action_name: &str) {
+        self.actions_with_effects.insert(action_name.to_string());
+    }
+
+    /// Returns every registered action that has not been marked as having an effect.
+    pub fn actions_without_effects(&self) -> Vec<String> {
+        self.get_action_names()
+            .into_iter()
+            .filter(|name| !self.actions_with_effects.contains(name))
+            .collect::<Vec<String>>()
+    }
+
+    /// Sets the default minimum time between two emissions of the same action.
+    /// Defaults to zero, meaning nothing is suppressed.
+    pub fn set_output_debounce(&mut self, debounce: Duration) {
+        self.timing.output_debounce = debounce;
+    }
+
+    /// Overrides the output debounce for a single action. `None` falls back to the default output debounce.
+    pub fn set_action_output_debounce(&mut self, action_name: &str, debounce: Option<Duration>) {
+        match debounce {
+            Some(d) => {
+                self.action_output_debounce
+                    .insert(action_name.to_string(), d);
+            }
+            None => {
+                self.action_output_debounce.remove(action_name);
+            }
+        }
+    }
+
+    /// Adds an action to an exclusive group, or removes it from its group if `group` is `None`.
+    ///
+    /// Only one action in an exclusive group is active at a time. When an action in the group fires,
+    /// `<previous action>:off` is emitted for the previously active member.
+    pub fn set_exclusive_group(&mut self, action_name: &str, group: Option<&str>) {
+        match group {
+            Some(group) => {
+                self.exclusive_groups
+                    .insert(action_name.to_string(), group.to_string());
+            }
+            None => {
+                if let Some(group) = self.exclusive_groups.remove(action_name) {
+                    if self.active_exclusive.get(&group).map(|(a, _)| a.as_str())
+                        == Some(action_name)
+                    {
+                        self.active_exclusive.remove(&group);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Only emits an action while the focused application matches `filter`, or always if `filter` is `None`.
+    ///
+    /// On Windows the filter is matched against the process name of the foreground window and on Linux against
+    /// its window class, ignoring case. If the focused application cannot be determined the action is skipped.
+    /// The action's keys stay hooked either way.
+    pub fn set_focus_filter(&mut self, action_name: &str, filter: Option<&str>) {
+        match filter {
+            Some(filter) => {
+                self.focus_filters
+                    .insert(action_name.to_string(), filter.to_string());
+            }
+            None => {
+                self.focus_filters.remove(action_name);
+            }
+        }
+    }
+
+    /// Sets a human readable description for an action, e.g. for showing in a settings UI. `None` removes it.
+    pub fn set_action_description(&mut self, action_name: &str, description: Option<&str>) {
+        match description {
+            Some(description) => {
+                self.descriptions
+                    .insert(action_name.to_string(), description.to_string());
+            }
+            None => {
+                self.descriptions.remove(action_name);
+            }
+        }
+    }
+
+    /// Gets the description of an action set with `set_action_description`.
+    pub fn action_description(&self, action_name: &str) -> Option<&str> {
+        self.descriptions.get(action_name).map(|d| d.as_str())
+    }
+
+    fn descriptions_for(&self, actions: &[String]) -> HashMap<String, String> {
+        actions
+            .iter()
+            .filter_map(|a| self.descriptions.get(a).map(|d| (a.clone(), d.clone())))
+            .collect()
+    }
+
+    /// Returns the minimum elapsed time as an `f32` in seconds.
+    pub fn get_min_elapsed_time(&self) -> f32 {
+        self.timing.min_elapsed_time.as_secs_f32()
+    }
+
+    /// Converts an `f32` into a `Duration`. Treats the `f32` as seconds.
+    pub fn set_min_elapsed_time(&mut self, min_elapsed_time: f32) {
+        self.timing.min_elapsed_time = Duration::from_secs_f32(min_elapsed_time);
+    }
+
+    /// Returns every timing setting.
+    pub fn timing(&self) -> &TimingConfig {
+        &self.timing
+    }
+
+    /// Replaces every timing setting at once.
+    pub fn set_timing(&mut self, timing: TimingConfig) {
+        self.timing = timing;
+    }
+
+    /// Lets chords be pressed one key at a time, with each key pressed within `grace` of the previous key
+    /// instead of every key pressed within the minimum elapsed time. Disabled by default.
+    ///
+    /// This is an accessibility option for users who cannot press several keys at once.
+    pub fn set_sequential_grace(&mut self, grace: Option<Duration>) {
+        self.timing.sequential_grace = grace;
+    }
+
+    /// Returns up to `n` of the most recently fired actions, oldest first.
+    pub fn recent_actions(&self, n: usize) -> Vec<ActionEvent> {
+        self.history
+            .iter()
+            .skip(self.history.len().saturating_sub(n))
+            .cloned()
+            .collect::<Vec<ActionEvent>>()
+    }
+
+    /// Returns how many times each action was emitted since the listener was created or `reset_counts`
+    /// was last called. Actions that never fired are left out. `:off` actions from exclusive groups are
+    /// counted under their own name.
+    pub fn trigger_counts(&self) -> HashMap<String, u64> {
+        self.trigger_counts.clone()
+    }
+
+    /// Resets every trigger count to zero.
+    pub fn reset_counts(&mut self) {
+        self.trigger_counts.clear();
+    }
+
+    /// Sets how many fired actions are kept in the action history. A capacity of `0` disables the history.
+    ///
+    /// The oldest actions are dropped if the history is larger than the new capacity.
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+
+        self.history_capacity = capacity;
+    }
+
+    /// Returns every registered key sequence along with its actions. The `id` of a binding
+    /// is the hash of its key sequence and can be passed to `unregister_chord`.
+    pub fn get_action_bindings(&self) -> Vec<ActionBinding> {
+        self.actions
+            .iter()
+            .map(|(hash, am)| ActionBinding {
+                id: *hash,
+                actions: am.actions.clone(),
+                keys: am.key_names.clone(),
+                ordered: am.ordered,
+                descriptions: self.descriptions_for(&am.actions),
+            })
+            .collect::<Vec<ActionBinding>>()
+    }
+
+    /// Lists every key name that can be used in a key sequence, e.g. for populating a dropdown of bindable keys.
+    ///
+    /// These are the canonical names accepted by `parse_key` rather than `KeyCode::as_str`, since display labels
+    /// like `⌫` cannot be parsed back. Key groups and aliases are not included.
+    pub fn valid_key_names() -> Vec<String> {
+        KEY_NAMES.iter().map(|name| name.to_string()).collect()
+    }
+
+    /// Lists chords that the OS on the current platform handles itself, e.g. `AltLeft + Tab`.
+    /// Registering one of these logs a warning.
+    pub fn reserved_chords() -> Vec<Vec<String>> {
+        RESERVED_CHORDS
+            .iter()
+            .map(|chord| chord.iter().map(|k| k.to_string()).collect())
+            .collect()
+    }
+
+    /// Finds pairs of actions whose keys overlap, meaning the keys of one action are the same as, or a subset of,
+    /// the keys of the other. Pressing the larger set of keys fires both actions.
+    ///
+    /// Each pair is listed once, sorted by action name.
+    pub fn find_conflicts(&self) -> Vec<(String, String)> {
+        let mappings = self.actions.values().collect::<Vec<&ActionMapping>>();
+        let is_subset = |a: &ActionMapping, b: &ActionMapping| {
+            a.slots.iter().all(|slot| b.slots.contains(slot))
+        };
+
+        let mut conflicts = vec![];
+        for (i, a) in mappings.iter().enumerate() {
+            for (j, first) in a.actions.iter().enumerate() {
+                for second in a.actions.iter().skip(j + 1) {
+                    conflicts.push((first.clone(), second.clone()));
+                }
+            }
+
+            for b in mappings.iter().skip(i + 1) {
+                if !is_subset(a, b) && !is_subset(b, a) {
+                    continue;
+                }
+
+                for first in a.actions.iter() {
+                    for second in b.actions.iter() {
+                        conflicts.push((first.clone(), second.clone()));
+                    }
+                }
+            }
+        }
+
+        for pair in conflicts.iter_mut() {
+            if pair.0 > pair.1 {
+                std::mem::swap(&mut pair.0, &mut pair.1);
+            }
+        }
+        // The same action bound to overlapping keys is not a conflict
+        conflicts.retain(|(first, second)| first != second);
+        conflicts.sort();
+        conflicts.dedup();
+
+        conflicts
+    }
+
+    /// Replays key presses against a copy of the current bindings and returns the actions that would fire,
+    /// in order. Each press is a key name and an offset from the start of the replay.
+    ///
+    /// Nothing is emitted and no state is changed. Key presses made before the replay are ignored, and output
+    /// debounce is applied using the replayed times.
+    pub fn would_fire(&self, presses: &[(String, Duration)]) -> Result<Vec<String>> {
+        let start = Instant::now();
+
+        let mut actions = self.actions.clone();
+        for am in actions.values_mut() {
+            for time in am.keys.values_mut() {
+                *time = start - Duration::from_secs(60);
+            }
+            am.reset_taps();
+            am.last_fired = None;
+        }
+
+        let mut last_emitted: HashMap<&String, Instant> = HashMap::new();
+        let mut fired = vec![];
+        for (name, offset) in presses.iter() {
+            let key = match self.key_groups.get(name) {
+                Some(group) if group.len() == 1 => group[0],
+                _ => parse_key(name)?,
+            };
+            let at = start + *offset;
+
+            let hashes = match self.reverse_lookup.get(&key) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            for hash in hashes.iter() {
+                let am = match actions.get_mut(hash) {
+                    Some(am) => am,
+                    None => return Err(Error::MappedKeyMissingInReverseLookup),
+                };
+
+                am.press_key_at(&key, at)?;
+                if !am.is_pressed(
+                    &self.timing.min_elapsed_time,
+                    self.timing.sequential_grace.as_ref(),
+                ) || !am.try_fire(at)
+                {
+                    continue;
+                }
+                am.reset_taps();
+
+                for action_name in self.actions[hash].actions.iter() {
+                    if am.disabled.contains(action_name) {
+                        continue;
+                    }
+                    let debounce = self
+                        .action_output_debounce
+                        .get(action_name)
+                        .unwrap_or(&self.timing.output_debounce);
+                    if let Some(last) = last_emitted.get(action_name) {
+                        if at.duration_since(*last) < *debounce {
+                            continue;
+                        }
+                    }
+
+                    last_emitted.insert(action_name, at);
+                    fired.push(action_name.clone());
+                }
+            }
+        }
+
+        Ok(fired)
+    }
+
+    /// Gets every registered key sequence split by where its actions were registered from. A key sequence
+    /// with actions from both the config and scripts appears under both sources with only the matching actions.
+    pub fn get_bindings_by_source(&self) -> HashMap<BindingSource, Vec<ActionBinding>> {
+        let mut bindings: HashMap<BindingSource, Vec<ActionBinding>> = HashMap::new();
+
+        for (hash, am) in self.actions.iter() {
+            for source in [BindingSource::Config, BindingSource::Script] {
+                let actions = am
+                    .actions
+                    .iter()
+                    .filter(|a| am.sources.get(*a) == Some(&source))
+                    .cloned()
+                    .collect::<Vec<String>>();
+                if actions.is_empty() {
+                    continue;
+                }
+
+                bindings.entry(source).or_default().push(ActionBinding {
+                    id: *hash,
+                    descriptions: self.descriptions_for(&actions),
+                    actions,
+                    keys: am.key_names.clone(),
+                    ordered: am.ordered,
+                });
+            }
+        }
+
+        bindings
+    }
+
+    /// Temporarily sets the minimum elapsed time until the returned guard is dropped.
+    /// The listener can be used through the guard in the meantime.
+    pub fn scoped_elapsed(&mut self, temp: Duration) -> ElapsedGuard<'_> {
+        let previous = std::mem::replace(&mut self.timing.min_elapsed_time, temp);
+
+        ElapsedGuard {
+            listener: self,
+            previous,
+        }
+    }
+
+    /// Sets the minimum elapsed time for the duration of `f`. The previous value is restored
+    /// afterwards, even if `f` panics.
+    pub fn with_elapsed_scope(&mut self, temp: Duration, f: impl FnOnce(&mut Self)) {
+        let mut guard = self.scoped_elapsed(temp);
+        f(&mut guard);
+    }
+
+    /// Iterates through all actions and returns a non-repeating `Vec` of all registered actions.
+    ///
+    /// The `Vec` is initially unsorted but is sorted in order to remove duplicates.
+    pub fn get_action_names(&self) -> Vec<String> {
+        let mut r = self
+            .actions
+            .values()
+            .into_iter()
+            .flat_map(|am| am.actions.clone())
+            .collect::<Vec<String>>();
+
+        r.sort_unstable();
+        r.dedup();
 
         r
     }
 
+    /// Gets every key sequence the action is registered to, using the key names it was registered with.
+    /// Returns an empty `Vec` if the action is not registered.
+    ///
+    /// Key sequences are _not_ sorted.
+    pub fn keys_for_action(&self, action_name: &str) -> Vec<Vec<String>> {
+        self.actions
+            .values()
+            .filter(|am| am.actions.iter().any(|a| a == action_name))
+            .map(|am| am.key_names.clone())
+            .collect::<Vec<Vec<String>>>()
+    }
+
+    /// Gets the keys pressed within the minimum elapsed time, i.e. the keys currently considered down.
+    /// Only keys used by a registered action are tracked. Meant for debugging, so it is not exact.
+    ///
+    /// Names are sorted.
+    pub fn held_keys(&self) -> Vec<String> {
+        let mut last_pressed: HashMap<KeyCode, Instant> = HashMap::new();
+        for am in self.actions.values() {
+            for (key, time) in am.keys.iter() {
+                let last = last_pressed.entry(*key).or_insert(*time);
+                *last = (*last).max(*time);
+            }
+        }
+
+        let mut names = last_pressed
+            .into_iter()
+            .filter(|(_, time)| time.elapsed() <= self.timing.min_elapsed_time)
+            .map(|(key, _)| key_name(key).to_string())
+            .collect::<Vec<String>>();
+        names.sort();
+
+        names
+    }
+
     /// Iterates through all reverse lookup keys and returns their names as a `Vec`.
     ///
     /// Names are _not_ sorted.
@@ -330,23 +2016,209 @@ impl HotkeyListener {
     }
 }
 
-/// Converts a `String` slice to a `Vec<String>` and then takes the hash of that `Vec`.
-/// Sorts the keys beforehand to ensure ordering doesn't impact the hash.
-fn string_slice_to_vec_and_hash(keys: &[String]) -> Result<(Vec<KeyCode>, u64)> {
+/// A `HotkeyListener` running on its own thread. See `HotkeyListener::spawn`.
+pub struct ListenerHandle {
+    stop: Arc<AtomicBool>,
+    join_handle: JoinHandle<Result<HotkeyListener>>,
+}
+
+impl ListenerHandle {
+    /// Whether the listener thread has exited, either because it was stopped or because polling failed.
+    pub fn is_finished(&self) -> bool {
+        self.join_handle.is_finished()
+    }
+
+    /// Stops the listener thread after its current poll and hands the listener back so it can be polled
+    /// manually or spawned again. Returns the error that stopped the thread if it exited on its own.
+    pub fn stop(self) -> Result<HotkeyListener> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.join()
+    }
+
+    /// Waits for the listener thread to exit without asking it to stop.
+    pub fn join(self) -> Result<HotkeyListener> {
+        match self.join_handle.join() {
+            Ok(result) => result,
+            Err(_) => Err(Error::ListenerThreadPanicked),
+        }
+    }
+}
+
+/// Restores the previous minimum elapsed time of a `HotkeyListener` when dropped.
+pub struct ElapsedGuard<'a> {
+    listener: &'a mut HotkeyListener,
+    previous: Duration,
+}
+
+impl Deref for ElapsedGuard<'_> {
+    type Target = HotkeyListener;
+
+    fn deref(&self) -> &Self::Target {
+        self.listener
+    }
+}
+
+impl DerefMut for ElapsedGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.listener
+    }
+}
+
+impl Drop for ElapsedGuard<'_> {
+    fn drop(&mut self) {
+        self.listener.timing.min_elapsed_time = self.previous;
+    }
+}
+
+/// Moves the value stored under `old` to `new`, if there is one.
+fn rename_key<V>(map: &mut HashMap<String, V>, old: &str, new: &str) {
+    if let Some(value) = map.remove(old) {
+        map.insert(new.to_string(), value);
+    }
+}
+
+/// Registers a key with the hook. Pressing the key sends it to `sender`.
+fn register_hook(hook: &Hook, key: KeyCode, sender: &Sender<KeyCode>) -> Result<()> {
+    let sender = sender.clone();
+    match hook.register(key, move || match sender.send(key) {
+        Ok(_) => {}
+        Err(e) => eprintln!("{e}"),
+    }) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(Error::CannotRegisterHotkey(e)),
+    }
+}
+
+/// Finds the reserved chord matching `key_slots`, if any. A chord matches if it has the same number of slots
+/// and every one of its keys is in one of the slots, so key groups containing the reserved keys also match.
+fn reserved_chord_for(key_slots: &[Vec<KeyCode>]) -> Option<&'static [&'static str]> {
+    RESERVED_CHORDS.iter().copied().find(|chord| {
+        chord.len() == key_slots.len()
+            && chord.iter().all(|name| match parse_key(name) {
+                Ok(code) => key_slots.iter().any(|slot| slot.contains(&code)),
+                Err(_) => false,
+            })
+    })
+}
+
+/// Converts a `String` slice to a `Vec` of key slots and then takes the hash of that `Vec`.
+/// Sorts and dedups the keys beforehand to ensure ordering and repeated keys don't impact the hash.
+/// If `ordered` is set, the keys are not sorted and the hash differs from the unordered one.
+///
+/// Names found in `key_groups` expand to a slot containing every key in the group, and generic modifiers
+/// like `Control` to a slot containing both sides of the modifier. Otherwise the name is parsed as a single
+/// `KeyCode`.
+pub fn string_slice_to_vec_and_hash(
+    keys: &[String],
+    key_groups: &HashMap<String, Vec<KeyCode>>,
+    ordered: bool,
+) -> Result<(Vec<Vec<KeyCode>>, u64)> {
     let mut keys = keys.to_vec();
-    keys.sort();
+    if !ordered {
+        keys.sort();
+        keys.dedup();
+    }
 
-    let mut key_codes = vec![];
+    let mut key_slots = vec![];
     for key in keys.iter() {
-        match KeyCode::from_str(key) {
-            Ok(k) => key_codes.push(k),
-            Err(_) => return Err(Error::BadKeyCodeName),
+        let slot = match (key_groups.get(key), Modifier::from_name(key)) {
+            (Some(group), _) => group.clone(),
+            (None, Some(modifier)) => modifier.keys(),
+            (None, None) => vec![parse_key(key)?],
         };
+
+        // Different names can refer to the same key, e.g. `A` and `KeyA`
+        if !key_slots.contains(&slot) {
+            key_slots.push(slot);
+        }
+    }
+
+    // Sorting the names is not enough since names that sort differently can resolve to the same keys,
+    // e.g. `ctrl` and `Control`
+    if !ordered {
+        key_slots.sort_by_key(|slot| slot.iter().map(|key| *key as u32).collect::<Vec<u32>>());
+    }
+
+    let key_codes_hash = if ordered {
+        get_hash(&(ordered, &key_slots))
+    } else {
+        get_hash(&key_slots)
+    };
+
+    Ok((key_slots, key_codes_hash))
+}
+
+/// A modifier that is satisfied by either its left or its right key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    Control,
+    Shift,
+    Alt,
+    Meta,
+}
+
+impl Modifier {
+    /// Parses a generic modifier name like `Control` or `ctrl`, ignoring case. Sided names like
+    /// `ControlLeft` are regular keys, not modifiers.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "control" | "ctrl" => Some(Modifier::Control),
+            "shift" => Some(Modifier::Shift),
+            "alt" | "option" => Some(Modifier::Alt),
+            "meta" | "super" | "cmd" | "command" | "win" | "windows" => Some(Modifier::Meta),
+            _ => None,
+        }
+    }
+
+    /// The physical keys that satisfy the modifier.
+    pub fn keys(self) -> Vec<KeyCode> {
+        match self {
+            Modifier::Control => vec![KeyCode::ControlLeft, KeyCode::ControlRight],
+            Modifier::Shift => vec![KeyCode::ShiftLeft, KeyCode::ShiftRight],
+            Modifier::Alt => vec![KeyCode::AltLeft, KeyCode::AltRight],
+            Modifier::Meta => vec![KeyCode::MetaLeft, KeyCode::MetaRight],
+        }
+    }
+}
+
+/// Parses a single key name, suggesting the closest valid name on failure.
+///
+/// Names are case-insensitive and common aliases like `ctrl`, `alt`, `cmd`/`win`, and `esc` are accepted.
+pub fn parse_key(key: &str) -> Result<KeyCode> {
+    if let Ok(k) = KeyCode::from_str(key) {
+        return Ok(k);
+    }
+
+    match key_names::normalize_key_name(key).map(KeyCode::from_str) {
+        Some(Ok(k)) => Ok(k),
+        _ => Err(Error::BadKeyCodeName {
+            name: key.to_string(),
+            suggestion: key_names::suggest_key_name(key),
+        }),
     }
+}
 
-    let key_codes_hash = get_hash(&key_codes);
+/// Gets the canonical name of a `KeyCode`, i.e. a name that can be passed back into `parse_key`.
+///
+/// Not to be confused with `KeyCode::as_str`, which returns a display label like `⌫` for `Backspace`.
+pub fn key_name(code: KeyCode) -> &'static str {
+    KEY_NAMES
+        .iter()
+        .find(|name| KeyCode::from_str(name) == Ok(code))
+        .copied()
+        .unwrap_or_else(|| code.as_str())
+}
+
+/// Flattens key slots into every physical key they contain, without duplicates.
+fn unique_key_codes(key_slots: &[Vec<KeyCode>]) -> Vec<KeyCode> {
+    let mut key_codes: Vec<KeyCode> = vec![];
+    for key in key_slots.iter().flatten() {
+        if !key_codes.contains(key) {
+            key_codes.push(*key);
+        }
+    }
 
-    Ok((key_codes, key_codes_hash))
+    key_codes
 }
 
 /// Gets the hash of some data using a new hasher.
@@ -356,3 +2228,580 @@ fn get_hash<T: Hash>(data: &T) -> u64 {
 
     hasher.finish()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listener() -> (HotkeyListener, Receiver<ActionEvent>) {
+        let (sender, receiver) = unbounded();
+        (HotkeyListener::new(sender).unwrap(), receiver)
+    }
+
+    fn keys(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    /// Registers `name` to `names` and returns the hash of its key sequence.
+    fn register(listener: &mut HotkeyListener, name: &str, names: &[&str]) -> u64 {
+        listener
+            .register_action(&name.to_string(), &keys(names))
+            .unwrap();
+        string_slice_to_vec_and_hash(&keys(names), &HashMap::new(), false)
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn exclusive_off_event_uses_previous_keys() {
+        let (mut listener, receiver) = listener();
+        listener.set_exclusive_group("a", Some("group"));
+        listener.set_exclusive_group("b", Some("group"));
+
+        listener
+            .emit_action("a".into(), vec!["KeyA".into()])
+            .unwrap();
+        listener
+            .emit_action("b".into(), vec!["KeyB".into()])
+            .unwrap();
+
+        let events = receiver
+            .try_iter()
+            .map(|e| (e.name, e.keys))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                ("a".to_string(), vec!["KeyA".to_string()]),
+                (format!("a{OFF_SUFFIX}"), vec!["KeyA".to_string()]),
+                ("b".to_string(), vec!["KeyB".to_string()]),
+            ]
+        );
+        assert_eq!(listener.trigger_counts()[&format!("a{OFF_SUFFIX}")], 1);
+        assert_eq!(listener.recent_actions(3).len(), 3);
+    }
+
+    #[test]
+    fn unregister_chord_removes_every_action_on_the_keys() {
+        let (mut listener, _receiver) = listener();
+        let hash = register(&mut listener, "a", &["KeyA", "KeyB"]);
+        register(&mut listener, "b", &["KeyA", "KeyB"]);
+        let other = register(&mut listener, "c", &["KeyB"]);
+
+        let ids = listener
+            .get_action_bindings()
+            .iter()
+            .map(|b| b.id)
+            .collect::<HashSet<u64>>();
+        assert_eq!(ids, HashSet::from([hash, other]));
+
+        listener.unregister_chord(hash).unwrap();
+        assert_eq!(listener.get_action_names(), vec!["c".to_string()]);
+        assert!(!listener.reverse_lookup.contains_key(&KeyCode::KeyA));
+        assert_eq!(listener.reverse_lookup[&KeyCode::KeyB], vec![other]);
+
+        assert!(matches!(
+            listener.unregister_chord(hash),
+            Err(Error::ActionDoesNotExist(MapType::Actions))
+        ));
+    }
+
+    #[test]
+    fn repeated_keys_hash_like_a_single_key() {
+        let groups = HashMap::new();
+        let (slots, hash) =
+            string_slice_to_vec_and_hash(&keys(&["a", "A", "a"]), &groups, false).unwrap();
+        let (single_slots, single_hash) =
+            string_slice_to_vec_and_hash(&keys(&["a"]), &groups, false).unwrap();
+
+        assert_eq!(slots, vec![vec![KeyCode::KeyA]]);
+        assert_eq!(slots, single_slots);
+        assert_eq!(hash, single_hash);
+    }
+
+    #[test]
+    fn ordered_keys_keep_their_order_when_deduped() {
+        let groups = HashMap::new();
+        let (slots, hash) =
+            string_slice_to_vec_and_hash(&keys(&["b", "a", "b"]), &groups, true).unwrap();
+        let (_, deduped_hash) =
+            string_slice_to_vec_and_hash(&keys(&["b", "a"]), &groups, true).unwrap();
+        let (_, reversed_hash) =
+            string_slice_to_vec_and_hash(&keys(&["a", "b"]), &groups, true).unwrap();
+
+        assert_eq!(slots, vec![vec![KeyCode::KeyB], vec![KeyCode::KeyA]]);
+        assert_eq!(hash, deduped_hash);
+        assert_ne!(hash, reversed_hash);
+    }
+
+    #[test]
+    fn taps_polled_together_are_not_collapsed() {
+        let (mut listener, _receiver) = listener();
+        listener.set_output_debounce(Duration::ZERO);
+        listener
+            .register_tap_action(
+                &"double".to_string(),
+                &"KeyA".to_string(),
+                2,
+                Duration::from_secs(1),
+            )
+            .unwrap();
+        listener
+            .register_action(&"single".to_string(), &keys(&["KeyB"]))
+            .unwrap();
+
+        for key in [KeyCode::KeyA, KeyCode::KeyA] {
+            listener.callback_sender.send(key).unwrap();
+        }
+        assert_eq!(listener.poll().unwrap(), vec!["double".to_string()]);
+
+        // Keys without tap actions are still collapsed
+        for key in [KeyCode::KeyB, KeyCode::KeyB] {
+            listener.callback_sender.send(key).unwrap();
+        }
+        assert_eq!(listener.poll().unwrap(), vec!["single".to_string()]);
+    }
+
+    #[test]
+    fn missing_mapping_is_an_error_not_a_panic() {
+        let (mut listener, _receiver) = listener();
+        let hash = register(&mut listener, "a", &["KeyA"]);
+        listener.actions.remove(&hash);
+
+        assert!(matches!(
+            listener.check_consistency(),
+            Err(Error::ActionDoesNotExist(MapType::ReverseLookup))
+        ));
+
+        listener.callback_sender.send(KeyCode::KeyA).unwrap();
+        assert!(listener.poll().unwrap().is_empty());
+
+        assert!(matches!(
+            listener.would_fire(&[("KeyA".to_string(), Duration::ZERO)]),
+            Err(Error::MappedKeyMissingInReverseLookup)
+        ));
+        assert!(matches!(
+            listener.unregister_action(&"a".to_string(), &keys(&["KeyA"])),
+            Err(Error::ActionDoesNotExist(MapType::Actions))
+        ));
+    }
+
+    #[test]
+    fn missing_reverse_lookup_is_an_error_not_a_panic() {
+        let (mut listener, _receiver) = listener();
+        register(&mut listener, "a", &["KeyA", "KeyB"]);
+        listener.reverse_lookup.remove(&KeyCode::KeyB);
+
+        assert!(matches!(
+            listener.check_consistency(),
+            Err(Error::MappedKeyMissingInReverseLookup)
+        ));
+
+        // Unregistering still cleans up what it can so the maps end up in sync again
+        assert!(matches!(
+            listener.unregister_action(&"a".to_string(), &keys(&["KeyA", "KeyB"])),
+            Err(Error::MappedKeyMissingInReverseLookup)
+        ));
+        assert!(listener.actions.is_empty());
+        assert!(listener.reverse_lookup.is_empty());
+        assert!(listener.check_consistency().is_ok());
+    }
+
+    #[test]
+    fn key_missing_from_mapping_is_skipped() {
+        let (mut listener, _receiver) = listener();
+        listener.set_output_debounce(Duration::ZERO);
+        let hash = register(&mut listener, "a", &["KeyA", "KeyB"]);
+        register(&mut listener, "b", &["KeyB"]);
+        listener
+            .actions
+            .get_mut(&hash)
+            .unwrap()
+            .keys
+            .remove(&KeyCode::KeyB);
+
+        for key in [KeyCode::KeyA, KeyCode::KeyB] {
+            listener.callback_sender.send(key).unwrap();
+        }
+        assert_eq!(listener.poll().unwrap(), vec!["b".to_string()]);
+    }
+
+    #[test]
+    fn registered_key_names_are_deduped() {
+        let (mut listener, _receiver) = listener();
+        listener
+            .register_action(&"chord".to_string(), &keys(&["KeyB", "KeyA", "KeyB"]))
+            .unwrap();
+        listener
+            .register_sequence(&"sequence".to_string(), &keys(&["KeyD", "KeyC", "KeyD"]))
+            .unwrap();
+
+        let mut bindings = listener
+            .get_action_bindings()
+            .into_iter()
+            .map(|b| (b.actions[0].clone(), b.keys, b.ordered))
+            .collect::<Vec<_>>();
+        bindings.sort();
+        assert_eq!(
+            bindings,
+            vec![
+                ("chord".to_string(), keys(&["KeyA", "KeyB"]), false),
+                ("sequence".to_string(), keys(&["KeyD", "KeyC"]), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn elapsed_scope_is_restored_after_a_panic() {
+        let (mut listener, _receiver) = listener();
+        let before = listener.timing().min_elapsed_time;
+        let widened = before * 10;
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            listener.with_elapsed_scope(widened, |listener| {
+                assert_eq!(listener.timing().min_elapsed_time, widened);
+                panic!("calibration failed");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(listener.timing().min_elapsed_time, before);
+
+        {
+            let guard = listener.scoped_elapsed(widened);
+            assert_eq!(guard.timing().min_elapsed_time, widened);
+        }
+        assert_eq!(listener.timing().min_elapsed_time, before);
+    }
+
+    #[test]
+    fn idle_hook_stays_healthy() {
+        let (mut listener, _receiver) = listener();
+        register(&mut listener, "a", &["KeyA"]);
+        listener.set_hook_health_threshold(Some(Duration::ZERO));
+
+        // No keys were pressed, which must not count as a dead hook
+        assert!(listener.hook_responds());
+        listener.poll().unwrap();
+        assert!(listener.hook_healthy());
+    }
+
+    #[test]
+    fn timing_round_trips() {
+        let (mut listener, _receiver) = listener();
+        let timing = TimingConfig {
+            min_elapsed_time: Duration::from_millis(50),
+            sequential_grace: Some(Duration::from_millis(300)),
+            output_debounce: Duration::from_millis(20),
+        };
+
+        listener.set_timing(timing.clone());
+        assert_eq!(listener.timing(), &timing);
+
+        // The single-setting setters change the same values
+        listener.set_output_debounce(Duration::ZERO);
+        listener.set_sequential_grace(None);
+        assert_eq!(
+            listener.timing(),
+            &TimingConfig {
+                min_elapsed_time: Duration::from_millis(50),
+                ..Default::default()
+            }
+        );
+
+        let text = toml::to_string(&timing).unwrap();
+        assert_eq!(
+            text,
+            "min_elapsed_time_ms = 50\nsequential_grace_ms = 300\noutput_debounce_ms = 20\n"
+        );
+        assert_eq!(toml::from_str::<TimingConfig>(&text).unwrap(), timing);
+        assert_eq!(
+            toml::from_str::<TimingConfig>("").unwrap(),
+            TimingConfig::default()
+        );
+    }
+
+    #[test]
+    fn disabled_actions_stay_hooked_but_are_not_emitted() {
+        let (mut listener, _receiver) = listener();
+        listener.set_output_debounce(Duration::ZERO);
+        register(&mut listener, "a", &["KeyA"]);
+        register(&mut listener, "b", &["KeyA"]);
+        let a = "a".to_string();
+
+        listener.disable_action(&a, &keys(&["KeyA"])).unwrap();
+        listener.callback_sender.send(KeyCode::KeyA).unwrap();
+        assert_eq!(listener.poll().unwrap(), vec!["b".to_string()]);
+        assert!(listener.reverse_lookup.contains_key(&KeyCode::KeyA));
+
+        listener.enable_action(&a, &keys(&["KeyA"])).unwrap();
+        listener.callback_sender.send(KeyCode::KeyA).unwrap();
+        assert_eq!(
+            listener.poll().unwrap(),
+            vec!["a".to_string(), "b".to_string()]
+        );
+
+        assert!(matches!(
+            listener.disable_action(&"c".to_string(), &keys(&["KeyA"])),
+            Err(Error::ActionDoesNotExist(MapType::ActionMapping))
+        ));
+        assert!(matches!(
+            listener.disable_action(&a, &keys(&["KeyB"])),
+            Err(Error::ActionDoesNotExist(MapType::Actions))
+        ));
+    }
+
+    #[test]
+    fn presses_while_paused_are_ignored() {
+        let (mut listener, _receiver) = listener();
+        listener.set_output_debounce(Duration::ZERO);
+        listener
+            .register_tap_action(
+                &"double".to_string(),
+                &"KeyA".to_string(),
+                2,
+                Duration::from_secs(1),
+            )
+            .unwrap();
+        register(&mut listener, "resume", &["KeyB"]);
+        listener.set_resume_action(Some("resume"));
+
+        listener.pause();
+        listener.callback_sender.send(KeyCode::KeyA).unwrap();
+        assert!(listener.poll().unwrap().is_empty());
+        assert!(listener.is_paused());
+
+        listener.callback_sender.send(KeyCode::KeyB).unwrap();
+        assert_eq!(listener.poll().unwrap(), vec!["resume".to_string()]);
+        assert!(!listener.is_paused());
+
+        // The tap made while paused does not count
+        listener.callback_sender.send(KeyCode::KeyA).unwrap();
+        assert!(listener.poll().unwrap().is_empty());
+        listener.callback_sender.send(KeyCode::KeyA).unwrap();
+        assert_eq!(listener.poll().unwrap(), vec!["double".to_string()]);
+    }
+
+    #[test]
+    fn failed_aliases_are_rolled_back() {
+        let (mut listener, _receiver) = listener();
+        let name = "a".to_string();
+        let combos = [keys(&["KeyA"]), keys(&["KeyB", "KeyC"]), keys(&["KeyA"])];
+
+        assert!(matches!(
+            listener.register_action_aliases(&name, &combos),
+            Err(Error::ActionAlreadyExists)
+        ));
+        assert!(listener.actions.is_empty());
+        assert!(listener.reverse_lookup.is_empty());
+
+        listener
+            .register_action_aliases(&name, &combos[..2])
+            .unwrap();
+        register(&mut listener, "b", &["KeyA"]);
+        assert_eq!(listener.keys_for_action("a").len(), 2);
+
+        listener.unregister_action_all(&name).unwrap();
+        assert_eq!(listener.get_action_names(), vec!["b".to_string()]);
+        assert_eq!(listener.reverse_lookup.len(), 1);
+        assert!(matches!(
+            listener.unregister_action_all(&name),
+            Err(Error::ActionDoesNotExist(MapType::Actions))
+        ));
+    }
+
+    #[test]
+    fn capture_records_keys_instead_of_firing() {
+        let (mut listener, _receiver) = listener();
+        register(&mut listener, "a", &["KeyA"]);
+
+        listener.with_elapsed_scope(Duration::from_secs(60), |listener| {
+            listener.start_capture();
+            for key in [KeyCode::KeyB, KeyCode::KeyA, KeyCode::KeyB] {
+                listener.callback_sender.send(key).unwrap();
+            }
+            assert!(listener.poll().unwrap().is_empty());
+
+            assert!(listener.is_capturing());
+            assert_eq!(listener.take_capture(), None);
+            listener.stop_capture();
+        });
+
+        assert!(!listener.is_capturing());
+        assert_eq!(listener.take_capture(), Some(keys(&["KeyB", "KeyA"])));
+        assert_eq!(listener.take_capture(), None);
+        // Only the keys of actions stay hooked
+        assert!(listener.passthrough_keys.is_empty());
+    }
+
+    #[test]
+    fn spawned_listener_stops_when_nobody_listens() {
+        let (mut listener, receiver) = listener();
+        register(&mut listener, "a", &["KeyA"]);
+        drop(receiver);
+        listener.callback_sender.send(KeyCode::KeyA).unwrap();
+
+        let handle = listener.spawn(Duration::from_millis(1));
+        let start = Instant::now();
+        while !handle.is_finished() {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "thread kept running"
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(matches!(handle.join(), Err(Error::ListenerChannelClosed)));
+    }
+
+    #[test]
+    fn clear_removes_every_binding() {
+        let (mut listener, _receiver) = listener();
+        listener.set_output_debounce(Duration::ZERO);
+        register(&mut listener, "a", &["KeyA", "KeyB"]);
+        let sequence = keys(&["KeyC", "KeyD"]);
+        listener
+            .register_sequence(&"s".to_string(), &sequence)
+            .unwrap();
+        let handled = Arc::new(AtomicBool::new(false));
+        let handler_flag = handled.clone();
+        listener.on("a", move |_| handler_flag.store(true, Ordering::Relaxed));
+        listener.set_exclusive_group("a", Some("group"));
+        listener
+            .emit_action("a".into(), keys(&["KeyA", "KeyB"]))
+            .unwrap();
+
+        // Start the sequence so there is progress to lose
+        listener.callback_sender.send(KeyCode::KeyC).unwrap();
+        listener.poll().unwrap();
+
+        handled.store(false, Ordering::Relaxed);
+
+        listener.clear().unwrap();
+        assert!(listener.actions.is_empty());
+        assert!(listener.reverse_lookup.is_empty());
+        assert!(listener.active_exclusive.is_empty());
+        assert!(listener.check_consistency().is_ok());
+
+        listener
+            .register_sequence(&"s".to_string(), &sequence)
+            .unwrap();
+        listener.callback_sender.send(KeyCode::KeyD).unwrap();
+        assert!(listener.poll().unwrap().is_empty());
+
+        // Handlers belong to the action name, so they still run once the action is registered again
+        register(&mut listener, "a", &["KeyA"]);
+        listener.callback_sender.send(KeyCode::KeyA).unwrap();
+        assert_eq!(listener.poll().unwrap(), vec!["a".to_string()]);
+        assert!(handled.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn renamed_actions_keep_their_keys_and_settings() {
+        let (mut listener, receiver) = listener();
+        register(&mut listener, "a", &["KeyA"]);
+        register(&mut listener, "c", &["KeyC"]);
+        listener.set_action_description("a", Some("Does a"));
+        listener.set_exclusive_group("a", Some("group"));
+        listener.set_exclusive_group("c", Some("group"));
+        let handled = Arc::new(AtomicBool::new(false));
+        let handler_flag = handled.clone();
+        listener.on("a", move |_| handler_flag.store(true, Ordering::Relaxed));
+
+        listener.callback_sender.send(KeyCode::KeyA).unwrap();
+        listener.poll().unwrap();
+        handled.store(false, Ordering::Relaxed);
+
+        listener.rename_action("a", "b").unwrap();
+        assert_eq!(
+            listener.get_action_names(),
+            vec!["b".to_string(), "c".to_string()]
+        );
+        assert_eq!(listener.action_description("b"), Some("Does a"));
+        assert_eq!(listener.action_description("a"), None);
+
+        listener.callback_sender.send(KeyCode::KeyA).unwrap();
+        assert_eq!(listener.poll().unwrap(), vec!["b".to_string()]);
+        assert!(handled.load(Ordering::Relaxed));
+
+        // The active member of the group was renamed too, so it is turned off under its new name
+        receiver.try_iter().for_each(drop);
+        listener.callback_sender.send(KeyCode::KeyC).unwrap();
+        listener.poll().unwrap();
+        let names = receiver.try_iter().map(|e| e.name).collect::<Vec<_>>();
+        assert_eq!(names, vec![format!("b{OFF_SUFFIX}"), "c".to_string()]);
+
+        register(&mut listener, "d", &["KeyA"]);
+        assert!(matches!(
+            listener.rename_action("b", "d"),
+            Err(Error::ActionAlreadyExists)
+        ));
+        assert!(matches!(
+            listener.rename_action("a", "e"),
+            Err(Error::ActionDoesNotExist(MapType::Actions))
+        ));
+    }
+
+    #[test]
+    fn concurrent_registrations_stay_consistent() {
+        let (listener, receiver) = listener();
+        let listener = Arc::new(std::sync::Mutex::new(listener));
+        let names = ["KeyA", "KeyB", "KeyC", "KeyD", "KeyE", "KeyF"];
+
+        let threads = (0..4)
+            .map(|t| {
+                let listener = listener.clone();
+                thread::spawn(move || {
+                    let mut emitted = 0;
+                    for i in 0..100 {
+                        let name = format!("t{t}_{i}");
+                        let keys = keys(&[names[(t + i) % names.len()], names[i % names.len()]]);
+
+                        let mut listener = listener.lock().unwrap();
+                        listener.register_action(&name, &keys).unwrap();
+                        listener.check_consistency().unwrap();
+
+                        for key in keys.iter() {
+                            listener
+                                .callback_sender
+                                .send(parse_key(key).unwrap())
+                                .unwrap();
+                        }
+                        emitted += listener.poll().unwrap().len();
+
+                        listener.unregister_action(&name, &keys).unwrap();
+                        listener.check_consistency().unwrap();
+                    }
+                    emitted
+                })
+            })
+            .collect::<Vec<_>>();
+        let emitted = threads
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .sum::<usize>();
+
+        let listener = listener.lock().unwrap();
+        assert!(listener.actions.is_empty());
+        assert!(listener.reverse_lookup.is_empty());
+        assert!(emitted > 0);
+        assert_eq!(receiver.try_iter().count(), emitted);
+        assert_eq!(
+            listener.trigger_counts().values().sum::<u64>(),
+            emitted as u64
+        );
+    }
+
+    #[test]
+    fn passthrough_gets_every_press() {
+        let (mut listener, _receiver) = listener();
+        let (sender, passthrough) = unbounded();
+        listener.set_passthrough(Some(sender));
+
+        let pressed = [KeyCode::KeyA, KeyCode::KeyA, KeyCode::KeyB];
+        for key in pressed {
+            listener.callback_sender.send(key).unwrap();
+        }
+        listener.poll().unwrap();
+
+        assert_eq!(passthrough.try_iter().collect::<Vec<_>>(), pressed);
+    }
+}
@@ -0,0 +1,351 @@
+/// Every name accepted by `KeyCode::from_str`, in declaration order.
+///
+/// `livesplit_hotkey` does not provide a way to enumerate `KeyCode`s so this has to be kept
+/// in sync by hand.
+pub const KEY_NAMES: &[&str] = &[
+    "Backquote",
+    "Backslash",
+    "Backspace",
+    "BracketLeft",
+    "BracketRight",
+    "Comma",
+    "Digit0",
+    "Digit1",
+    "Digit2",
+    "Digit3",
+    "Digit4",
+    "Digit5",
+    "Digit6",
+    "Digit7",
+    "Digit8",
+    "Digit9",
+    "Equal",
+    "IntlBackslash",
+    "IntlRo",
+    "IntlYen",
+    "KeyA",
+    "KeyB",
+    "KeyC",
+    "KeyD",
+    "KeyE",
+    "KeyF",
+    "KeyG",
+    "KeyH",
+    "KeyI",
+    "KeyJ",
+    "KeyK",
+    "KeyL",
+    "KeyM",
+    "KeyN",
+    "KeyO",
+    "KeyP",
+    "KeyQ",
+    "KeyR",
+    "KeyS",
+    "KeyT",
+    "KeyU",
+    "KeyV",
+    "KeyW",
+    "KeyX",
+    "KeyY",
+    "KeyZ",
+    "Minus",
+    "Period",
+    "Quote",
+    "Semicolon",
+    "Slash",
+    "AltLeft",
+    "AltRight",
+    "CapsLock",
+    "ContextMenu",
+    "ControlLeft",
+    "ControlRight",
+    "Enter",
+    "MetaLeft",
+    "MetaRight",
+    "ShiftLeft",
+    "ShiftRight",
+    "Space",
+    "Tab",
+    "Convert",
+    "KanaMode",
+    "Lang1",
+    "Lang2",
+    "Lang3",
+    "Lang4",
+    "Lang5",
+    "NonConvert",
+    "Delete",
+    "End",
+    "Help",
+    "Home",
+    "Insert",
+    "PageDown",
+    "PageUp",
+    "ArrowDown",
+    "ArrowLeft",
+    "ArrowRight",
+    "ArrowUp",
+    "NumLock",
+    "Numpad0",
+    "Numpad1",
+    "Numpad2",
+    "Numpad3",
+    "Numpad4",
+    "Numpad5",
+    "Numpad6",
+    "Numpad7",
+    "Numpad8",
+    "Numpad9",
+    "NumpadAdd",
+    "NumpadBackspace",
+    "NumpadClear",
+    "NumpadClearEntry",
+    "NumpadComma",
+    "NumpadDecimal",
+    "NumpadDivide",
+    "NumpadEnter",
+    "NumpadEqual",
+    "NumpadHash",
+    "NumpadMemoryAdd",
+    "NumpadMemoryClear",
+    "NumpadMemoryRecall",
+    "NumpadMemoryStore",
+    "NumpadMemorySubtract",
+    "NumpadMultiply",
+    "NumpadParenLeft",
+    "NumpadParenRight",
+    "NumpadStar",
+    "NumpadSubtract",
+    "Escape",
+    "F1",
+    "F2",
+    "F3",
+    "F4",
+    "F5",
+    "F6",
+    "F7",
+    "F8",
+    "F9",
+    "F10",
+    "F11",
+    "F12",
+    "F13",
+    "F14",
+    "F15",
+    "F16",
+    "F17",
+    "F18",
+    "F19",
+    "F20",
+    "F21",
+    "F22",
+    "F23",
+    "F24",
+    "Fn",
+    "FnLock",
+    "PrintScreen",
+    "ScrollLock",
+    "Pause",
+    "BrowserBack",
+    "BrowserFavorites",
+    "BrowserForward",
+    "BrowserHome",
+    "BrowserRefresh",
+    "BrowserSearch",
+    "BrowserStop",
+    "Eject",
+    "LaunchApp1",
+    "LaunchApp2",
+    "LaunchMail",
+    "MediaPlayPause",
+    "MediaSelect",
+    "MediaStop",
+    "MediaTrackNext",
+    "MediaTrackPrevious",
+    "Power",
+    "Sleep",
+    "AudioVolumeDown",
+    "AudioVolumeMute",
+    "AudioVolumeUp",
+    "WakeUp",
+    "Again",
+    "Copy",
+    "Cut",
+    "Find",
+    "Open",
+    "Paste",
+    "Props",
+    "Select",
+    "Undo",
+    "Gamepad0",
+    "Gamepad1",
+    "Gamepad2",
+    "Gamepad3",
+    "Gamepad4",
+    "Gamepad5",
+    "Gamepad6",
+    "Gamepad7",
+    "Gamepad8",
+    "Gamepad9",
+    "Gamepad10",
+    "Gamepad11",
+    "Gamepad12",
+    "Gamepad13",
+    "Gamepad14",
+    "Gamepad15",
+    "Gamepad16",
+    "Gamepad17",
+    "Gamepad18",
+    "Gamepad19",
+    "BrightnessDown",
+    "BrightnessUp",
+    "DisplayToggleIntExt",
+    "KeyboardLayoutSelect",
+    "LaunchAssistant",
+    "LaunchControlPanel",
+    "LaunchScreenSaver",
+    "MailForward",
+    "MailReply",
+    "MailSend",
+    "MediaFastForward",
+    "MediaPause",
+    "MediaPlay",
+    "MediaRecord",
+    "MediaRewind",
+    "PrivacyScreenToggle",
+    "SelectTask",
+    "ShowAllWindows",
+    "ZoomToggle",
+];
+
+/// Common names for keys that `KeyCode::from_str` does not accept, lowercased, and the key name they stand for.
+/// Modifiers default to the left key here. Inside an action's keys they match either side instead, see `Modifier`.
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("ctrl", "ControlLeft"),
+    ("control", "ControlLeft"),
+    ("shift", "ShiftLeft"),
+    ("alt", "AltLeft"),
+    ("option", "AltLeft"),
+    ("cmd", "MetaLeft"),
+    ("command", "MetaLeft"),
+    ("win", "MetaLeft"),
+    ("windows", "MetaLeft"),
+    ("super", "MetaLeft"),
+    ("meta", "MetaLeft"),
+    ("esc", "Escape"),
+    ("return", "Enter"),
+    ("del", "Delete"),
+    ("ins", "Insert"),
+    ("pgup", "PageUp"),
+    ("pgdn", "PageDown"),
+    ("up", "ArrowUp"),
+    ("down", "ArrowDown"),
+    ("left", "ArrowLeft"),
+    ("right", "ArrowRight"),
+];
+
+/// Maps a loosely written key name to the name `KeyCode::from_str` accepts, e.g. `ctrl` to `ControlLeft`,
+/// `ESCAPE` to `Escape`, and `a` to `KeyA`. Case is ignored.
+pub fn normalize_key_name(name: &str) -> Option<&'static str> {
+    let lower = name.to_lowercase();
+
+    if let Some((_, key_name)) = KEY_ALIASES.iter().find(|(alias, _)| *alias == lower) {
+        return Some(key_name);
+    }
+
+    KEY_NAMES.iter().copied().find(|candidate| {
+        let candidate = candidate.to_lowercase();
+        candidate == lower
+            || ["key", "digit"]
+                .iter()
+                .any(|prefix| candidate.strip_prefix(prefix) == Some(lower.as_str()))
+    })
+}
+
+/// Gets the name of a `KeyCode` by its discriminant, i.e. its position in the `KeyCode` enum.
+pub fn key_name_for_code(code: u32) -> Option<&'static str> {
+    KEY_NAMES.get(code as usize).copied()
+}
+
+/// Finds the valid key name closest to `name`. Returns `None` if nothing is close enough to
+/// plausibly be a typo.
+///
+/// Common prefixes/suffixes (`Key`, `Digit`, `Left`, `Right`) are also stripped from candidates so
+/// that something like `Shfit` can be matched to `ShiftLeft`.
+pub fn suggest_key_name(name: &str) -> Option<String> {
+    let name = name.to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    let mut best: Option<(usize, &str)> = None;
+    for candidate in KEY_NAMES.iter() {
+        let lower = candidate.to_lowercase();
+
+        let distance = candidate_forms(&lower)
+            .iter()
+            .map(|form| edit_distance(&name, form))
+            .min()
+            .unwrap_or(usize::MAX);
+
+        if distance <= max_distance
+            && distance < name.chars().count()
+            && match best {
+                Some((d, _)) => distance < d,
+                None => true,
+            }
+        {
+            best = Some((distance, candidate));
+        }
+    }
+
+    best.map(|(_, candidate)| candidate.to_string())
+}
+
+/// All the forms a lowercased key name can reasonably be typed as.
+fn candidate_forms(name: &str) -> Vec<&str> {
+    let mut forms = vec![name];
+
+    for prefix in ["key", "digit"] {
+        if let Some(stripped) = name.strip_prefix(prefix) {
+            forms.push(stripped);
+        }
+    }
+    for suffix in ["left", "right"] {
+        if let Some(stripped) = name.strip_suffix(suffix) {
+            forms.push(stripped);
+        }
+    }
+
+    forms
+}
+
+/// Optimal string alignment distance, i.e. Levenshtein distance that also counts transposing
+/// two adjacent characters as a single edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<char>>();
+    let b = b.chars().collect::<Vec<char>>();
+
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
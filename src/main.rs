@@ -1,20 +1,77 @@
+#[cfg(feature = "audio")]
+mod audio;
+mod autostart;
+#[cfg(feature = "bundle")]
+mod bundle;
+mod config_watcher;
+mod focus;
 mod hotkey_listener;
+mod migrate;
 #[cfg(feature = "scripting")]
 mod scripting;
+mod sink;
 mod utils;
 
-use std::{error::Error, fmt::Display, io::Write};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError};
 use directories::ProjectDirs;
 use log::{debug, error, info};
 use serde::{Deserialize, Serialize};
 
+use config_watcher::{ConfigWatcher, DirWatcher};
+use hotkey_listener::{ActionEvent, BindingSource, HotkeyListener, Modifier, TimingConfig};
+use livesplit_hotkey::KeyCode;
+use scripting::ListenerCommand;
+use sink::{ActionSink, AppendFile, AppendFileSink, FifoSink};
+
 const GIT_REV: &str = env!("GIT_REV");
 const BUILD_NAME: &str = env!("BUILD_NAME");
 const PROGRAM_NAME: &str = "viraction";
 
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// How often the config file is checked for changes.
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Overrides the path of the config file, like `--config`.
+const CONFIG_ENV_VAR: &str = "VIRACTION_CONFIG";
+
+/// How close together taps have to be for tap actions without a `window_ms`.
+const DEFAULT_TAP_WINDOW_MS: u64 = 300;
+/// How long a script may run in one call when the config does not set `script_timeout_ms`.
+const DEFAULT_SCRIPT_TIMEOUT_MS: u64 = 2000;
+/// How long `--capture` waits for keys to be pressed.
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How close together the keys captured by `--capture` have to be. Wider than the usual chord window since
+/// people asked to press a chord tend to press its keys one at a time.
+const CAPTURE_WINDOW: Duration = Duration::from_secs(1);
+/// An action with this name pauses every other action, until it fires again.
+const TOGGLE_PAUSE_ACTION: &str = "toggle_pause";
+
+/// Written at the top of the initial config since serializing a `Config` cannot add comments.
+const DEFAULT_CONFIG_HEADER: &str = "\
+# viraction config
+#
+# The actions below are built-in defaults to get started with. Edit or remove them as you like.
+# toggle_pause stops every other hotkey from firing until it is pressed again, e.g. while typing.
+# Run viraction with --no-defaults to create an empty config instead.
+
+";
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum ViractionError {
+    /// The named action sets both `keys` and `keycodes`.
+    KeysAndKeycodes(String),
+    /// The named action uses a keycode that no key has.
+    InvalidKeycode {
+        action: String,
+        code: u32,
+    },
     Other(String),
 }
 
@@ -23,16 +80,121 @@ impl Error for ViractionError {}
 impl Display for ViractionError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ViractionError::Other(s) => write!(f, "Other: {}", s),
+            ViractionError::KeysAndKeycodes(action) => {
+                write!(f, "{} uses both keys and keycodes", action)
+            }
+            ViractionError::InvalidKeycode { action, code } => {
+                write!(f, "{} uses invalid keycode {}", action, code)
+            }
+            ViractionError::Other(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// A problem found by `Config::validate`.
+#[derive(Debug)]
+enum ConfigError {
+    /// The action at this position in `actions` has no name.
+    EmptyName(usize),
+    DuplicateName(String),
+    /// A key that cannot be parsed, in the named action, key group, or alias.
+    BadKey {
+        owner: String,
+        error: hotkey_listener::Error,
+    },
+    /// The keys of an action cannot be used, either `KeysAndKeycodes` or `InvalidKeycode`.
+    BadKeys(ViractionError),
+    SameKeys {
+        first: String,
+        second: String,
+    },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::EmptyName(index) => write!(f, "action #{} has no name", index + 1),
+            ConfigError::DuplicateName(name) => {
+                write!(f, "action {} is defined more than once", name)
+            }
+            ConfigError::BadKey { owner, error } => write!(f, "{}: {}", owner, error),
+            ConfigError::BadKeys(e) => write!(f, "{}", e),
+            ConfigError::SameKeys { first, second } => {
+                write!(f, "actions {} and {} have the same keys", first, second)
+            }
         }
     }
 }
 
 // Used to register hotkeys with the OS.
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct Action {
     name: String,
+    /// What the action does, shown wherever actions are listed.
+    description: Option<String>,
+    /// Disabled actions are kept in the config but not registered. Defaults to `true`.
+    #[serde(default = "Action::default_enabled")]
+    enabled: bool,
+    /// Key names refer to physical key positions (scancodes), not the character a key produces.
+    /// e.g. `KeyQ` is the key to the right of `Tab` on both QWERTY and AZERTY layouts,
+    /// so configs can be shared between keyboard layouts.
+    ///
+    /// `Control`, `Shift`, `Alt`, and `Meta` match either the left or the right key.
     keys: Vec<String>,
+    /// More key sequences that fire the action, e.g. `[["F13"], ["ControlLeft", "KeyM"]]`.
+    /// Not supported for ordered and tap actions, or together with `window_ms` or `cooldown_ms`.
+    #[serde(default)]
+    alternative_keys: Vec<Vec<String>>,
+    /// Keys as `KeyCode` discriminants, for keys without a usable name. Cannot be used with `keys`.
+    #[serde(default)]
+    keycodes: Vec<u32>,
+    /// The keys must be pressed in the order they are listed.
+    #[serde(default)]
+    ordered: bool,
+    /// How close together the keys have to be pressed. Overrides `timing.min_elapsed_time_ms`.
+    /// Not supported for ordered actions. For tap actions, how close together the taps have to be.
+    window_ms: Option<u64>,
+    /// Fire when the single key in `keys` is pressed this many times within `window_ms`,
+    /// which defaults to 300ms.
+    taps: Option<u32>,
+    /// Only one action in an exclusive group is active at a time. Firing an action turns off
+    /// the previously active action in the same group.
+    group_exclusive: Option<String>,
+    /// Overrides `timing.output_debounce_ms` for this action.
+    output_debounce_ms: Option<u64>,
+    /// How long the keys cannot fire again after firing, e.g. so a held chord does not fire on every extra
+    /// key press. Not supported for ordered and tap actions, or together with `window_ms`.
+    cooldown_ms: Option<u64>,
+    /// Only fire while an application matching this name is focused, e.g. `obs`.
+    focus_filter: Option<String>,
+    /// Sound file to play when the action fires.
+    #[cfg(feature = "audio")]
+    sound: Option<PathBuf>,
+    /// File to append a line to when the action fires.
+    append_file: Option<AppendFile>,
+}
+
+impl Default for Action {
+    fn default() -> Self {
+        Action {
+            name: String::new(),
+            description: None,
+            enabled: Action::default_enabled(),
+            keys: vec![],
+            alternative_keys: vec![],
+            keycodes: vec![],
+            ordered: false,
+            window_ms: None,
+            taps: None,
+            group_exclusive: None,
+            output_debounce_ms: None,
+            cooldown_ms: None,
+            focus_filter: None,
+            #[cfg(feature = "audio")]
+            sound: None,
+            append_file: None,
+        }
+    }
 }
 
 impl Action {
@@ -40,20 +202,877 @@ impl Action {
         Action {
             name: name.clone(),
             keys: keys.into_iter().map(|x| String::from(*x)).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn default_enabled() -> bool {
+        true
+    }
+
+    /// Gets the key names for the action from either `keys` or `keycodes`.
+    fn key_names(&self) -> Result<Vec<String>, ViractionError> {
+        if !self.keycodes.is_empty() && !self.keys.is_empty() {
+            return Err(ViractionError::KeysAndKeycodes(self.name.clone()));
+        }
+
+        if self.keycodes.is_empty() {
+            return Ok(self.keys.clone());
         }
+
+        let mut names = vec![];
+        for code in self.keycodes.iter() {
+            match hotkey_listener::key_name_for_code(*code) {
+                Some(name) => names.push(name.to_string()),
+                None => {
+                    return Err(ViractionError::InvalidKeycode {
+                        action: self.name.clone(),
+                        code: *code,
+                    })
+                }
+            }
+        }
+
+        Ok(names)
     }
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct Config {
+    /// Version of the config format, used to upgrade configs written by older versions.
+    #[serde(default = "Config::current_version")]
+    version: u32,
+    /// Start viraction when logging in. The OS setting is updated to match every time viraction starts.
     run_at_startup: bool,
+    /// How long to wait before hooking into the OS. Useful if hooking fails when launched at startup.
+    startup_delay_ms: Option<u64>,
+    /// FIFO/named pipe that fired action names are written to, one per line.
+    fifo_sink: Option<PathBuf>,
+    /// How many fired actions to keep in memory for debugging. Defaults to 100.
+    action_history_size: Option<usize>,
+    /// How often to check that the OS hook still works, recreating it if it stopped. Disabled by default.
+    hook_health_threshold_secs: Option<u64>,
+    /// How long a script may run each time it is called, e.g. for an action, before it is stopped.
+    /// Defaults to 2000.
+    script_timeout_ms: Option<u64>,
     actions: Vec<Action>,
+    /// When chords fire, see `TimingConfig`.
+    #[serde(default)]
+    timing: TimingConfig,
+    /// Logical key names that are satisfied by any one of the listed physical keys.
+    /// e.g. `AnyEnter = ["Enter", "NumpadEnter"]`
+    #[serde(default)]
+    key_groups: HashMap<String, Vec<String>>,
+    /// Custom names for keys, e.g. `MyMacroKey = "F13"`
+    #[serde(default)]
+    aliases: HashMap<String, String>,
 }
 
 impl Config {
     fn new() -> Self {
-        Config::default()
+        Config {
+            version: migrate::CONFIG_VERSION,
+            ..Default::default()
+        }
+    }
+
+    fn current_version() -> u32 {
+        migrate::CONFIG_VERSION
+    }
+
+    fn script_timeout(&self) -> Duration {
+        Duration::from_millis(self.script_timeout_ms.unwrap_or(DEFAULT_SCRIPT_TIMEOUT_MS))
+    }
+
+    /// Writes the config to `path` in the format matching its extension, see `utils::write_atomic`.
+    /// The previous config is kept as a `.bak` file next to it.
+    ///
+    /// Comments in the existing file are not preserved.
+    fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let contents = ConfigFormat::from_path(path).serialize(self)?;
+
+        utils::write_atomic(path, contents.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Checks every action, key group, and alias, collecting every problem instead of stopping at the first one.
+    ///
+    /// Actions must have a unique, non-empty name and keys that parse. No two enabled actions may have the same keys.
+    fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = vec![];
+
+        // Bad aliases and groups are still inserted so actions using them are not reported again
+        let mut key_groups: HashMap<String, Vec<KeyCode>> = HashMap::new();
+        for (alias, key) in self.aliases.iter() {
+            match hotkey_listener::parse_key(key) {
+                Ok(k) => {
+                    key_groups.insert(alias.clone(), vec![k]);
+                }
+                Err(error) => {
+                    key_groups.insert(alias.clone(), vec![]);
+                    errors.push(ConfigError::BadKey {
+                        owner: format!("alias {}", alias),
+                        error,
+                    });
+                }
+            }
+        }
+        for (group_name, keys) in self.key_groups.iter() {
+            let mut codes = vec![];
+            for key in keys.iter() {
+                match hotkey_listener::parse_key(key) {
+                    Ok(k) => codes.push(k),
+                    Err(error) => errors.push(ConfigError::BadKey {
+                        owner: format!("key group {}", group_name),
+                        error,
+                    }),
+                }
+            }
+            key_groups.insert(group_name.clone(), codes);
+        }
+
+        let mut names: HashMap<&String, usize> = HashMap::new();
+        let mut key_sets: HashMap<(u64, Option<u32>), &String> = HashMap::new();
+        for (i, action) in self.actions.iter().enumerate() {
+            let owner = if action.name.is_empty() {
+                format!("action #{}", i + 1)
+            } else {
+                format!("action {}", action.name)
+            };
+
+            if action.name.is_empty() {
+                errors.push(ConfigError::EmptyName(i));
+            } else if names.insert(&action.name, i).is_some() {
+                errors.push(ConfigError::DuplicateName(action.name.clone()));
+            }
+
+            let keys = match action.key_names() {
+                Ok(k) => k,
+                Err(e) => {
+                    errors.push(ConfigError::BadKeys(e));
+                    continue;
+                }
+            };
+
+            let mut keys_ok = true;
+            for key in keys.iter() {
+                if key_groups.contains_key(key) || Modifier::from_name(key).is_some() {
+                    continue;
+                }
+                if let Err(error) = hotkey_listener::parse_key(key) {
+                    errors.push(ConfigError::BadKey {
+                        owner: owner.clone(),
+                        error,
+                    });
+                    keys_ok = false;
+                }
+            }
+            for key in action.alternative_keys.iter().flatten() {
+                if key_groups.contains_key(key) || Modifier::from_name(key).is_some() {
+                    continue;
+                }
+                if let Err(error) = hotkey_listener::parse_key(key) {
+                    errors.push(ConfigError::BadKey {
+                        owner: owner.clone(),
+                        error,
+                    });
+                }
+            }
+
+            if !keys_ok || !action.enabled {
+                continue;
+            }
+
+            if let Ok((_, hash)) =
+                hotkey_listener::string_slice_to_vec_and_hash(&keys, &key_groups, action.ordered)
+            {
+                if let Some(first) = key_sets.insert((hash, action.taps), &action.name) {
+                    errors.push(ConfigError::SameKeys {
+                        first: first.clone(),
+                        second: action.name.clone(),
+                    });
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns the config as it is actually used, with `keycodes` converted to key names and
+    /// aliases replaced by the keys they refer to.
+    fn resolved(&self) -> Result<Config, ViractionError> {
+        let mut config = self.clone();
+
+        for action in config.actions.iter_mut() {
+            action.keys = action
+                .key_names()?
+                .into_iter()
+                .map(|key| self.aliases.get(&key).cloned().unwrap_or(key))
+                .collect();
+            action.keycodes.clear();
+
+            for key in action.alternative_keys.iter_mut().flatten() {
+                if let Some(aliased) = self.aliases.get(key) {
+                    *key = aliased.clone();
+                }
+            }
+        }
+
+        for keys in config.key_groups.values_mut() {
+            for key in keys.iter_mut() {
+                if let Some(aliased) = self.aliases.get(key) {
+                    *key = aliased.clone();
+                }
+            }
+        }
+
+        config.aliases.clear();
+
+        Ok(config)
+    }
+
+    /// Creates a config seeded with a few example actions for first-time users.
+    fn with_defaults() -> Self {
+        let action = |name: &str, description: &str, keys: &[&str]| Action {
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            ..Default::default()
+        };
+
+        Config {
+            actions: vec![action(
+                TOGGLE_PAUSE_ACTION,
+                "Pauses every other hotkey until pressed again",
+                &["Control", "Shift", "Pause"],
+            )],
+            ..Config::new()
+        }
+    }
+}
+
+/// Logs every registered key sequence with its id, which `unregister_chord` takes, and its actions.
+/// Actions with a description are listed with it.
+fn log_bindings(listener: &HotkeyListener) {
+    let mut bindings = listener.get_action_bindings();
+    bindings.sort_by(|a, b| a.keys.cmp(&b.keys));
+    for binding in bindings.iter() {
+        let actions = binding
+            .actions
+            .iter()
+            .map(|a| match binding.descriptions.get(a) {
+                Some(description) => format!("{a} ({description})"),
+                None => a.clone(),
+            })
+            .collect::<Vec<String>>();
+        debug!(
+            "Binding {:016x}: {}{} -> {}",
+            binding.id,
+            binding.keys.join(" + "),
+            if binding.ordered { " (in order)" } else { "" },
+            actions.join(", ")
+        );
+    }
+}
+
+/// Gets the value following a command line flag, e.g. `--flag value`.
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+}
+
+/// The formats a config file can be written in, picked by the extension of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    fn file_name(self) -> &'static str {
+        match self {
+            ConfigFormat::Toml => "config.toml",
+            ConfigFormat::Json => "config.json",
+        }
+    }
+
+    fn serialize(self, config: &Config) -> Result<String, Box<dyn Error>> {
+        match self {
+            ConfigFormat::Toml => Ok(toml::to_string_pretty(config)?),
+            ConfigFormat::Json => Ok(serde_json::to_string_pretty(config)? + "\n"),
+        }
+    }
+
+    /// Parses a config into a TOML value no matter the format, so migrations only deal with one shape.
+    fn parse(self, text: &str) -> Result<toml::Value, Box<dyn Error>> {
+        match self {
+            ConfigFormat::Toml => Ok(toml::from_str(text)?),
+            ConfigFormat::Json => match json_to_toml(serde_json::from_str(text)?)? {
+                Some(value) => Ok(value),
+                None => Err(Box::new(ViractionError::Other(
+                    "config cannot be null".to_string(),
+                ))),
+            },
+        }
+    }
+}
+
+/// Converts JSON to TOML, dropping `null`s since TOML has no equivalent. A missing value deserializes the
+/// same as a `null` would.
+///
+/// Integers too large for TOML are errors, since turning them into floats would only fail later with a
+/// confusing type error.
+fn json_to_toml(value: serde_json::Value) -> Result<Option<toml::Value>, ViractionError> {
+    use serde_json::Value as Json;
+
+    Ok(Some(match value {
+        Json::Null => return Ok(None),
+        Json::Bool(b) => toml::Value::Boolean(b),
+        Json::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => toml::Value::Integer(i),
+            (None, _) if n.is_u64() => {
+                return Err(ViractionError::Other(format!(
+                    "{} is too large, numbers can be at most {}",
+                    n,
+                    i64::MAX
+                )))
+            }
+            (None, Some(f)) => toml::Value::Float(f),
+            (None, None) => return Ok(None),
+        },
+        Json::String(s) => toml::Value::String(s),
+        Json::Array(values) => {
+            let mut array = vec![];
+            for value in values {
+                array.extend(json_to_toml(value)?);
+            }
+            toml::Value::Array(array)
+        }
+        Json::Object(map) => {
+            let mut table = toml::value::Table::new();
+            for (key, value) in map {
+                if let Some(value) = json_to_toml(value)? {
+                    table.insert(key, value);
+                }
+            }
+            toml::Value::Table(table)
+        }
+    }))
+}
+
+/// Reads the config at `path`, upgrading it to the current version first. Upgraded configs are written back
+/// to `path` with the original kept next to it as a `.bak` file.
+fn read_config(path: &Path) -> Result<Config, Box<dyn Error>> {
+    let config = std::fs::read_to_string(path)?;
+
+    debug!("{}", config);
+
+    let (config, migrated) = parse_config(&config, ConfigFormat::from_path(path))?;
+
+    if migrated {
+        config.save(path)?;
+        info!(
+            "Upgraded config to version {}, the original was saved to {}.bak",
+            config.version,
+            path.display()
+        );
+    }
+
+    Ok(config)
+}
+
+/// Reads the config at `path` as it is actually used, see `Config::resolved`. Unlike `read_config`, an older
+/// config is only upgraded in memory, so looking at the effective config never rewrites the file.
+fn effective_config(path: &Path) -> Result<Config, Box<dyn Error>> {
+    let (config, _) = parse_config(
+        &std::fs::read_to_string(path)?,
+        ConfigFormat::from_path(path),
+    )?;
+
+    Ok(config.resolved()?)
+}
+
+/// Parses a config, upgrading it to the current version if it is older. Returns whether it was upgraded.
+fn parse_config(text: &str, format: ConfigFormat) -> Result<(Config, bool), Box<dyn Error>> {
+    let mut value = format.parse(text)?;
+    let migrated = migrate::migrate(&mut value);
+
+    Ok((value.try_into()?, migrated))
+}
+
+fn create_sinks(config: &Config) -> Vec<Box<dyn ActionSink>> {
+    let mut sinks: Vec<Box<dyn ActionSink>> = vec![];
+    if let Some(path) = config.fifo_sink.as_ref() {
+        info!("Writing actions to {}", path.display());
+        sinks.push(Box::new(FifoSink::new(path)));
+    }
+
+    let append_files = config
+        .actions
+        .iter()
+        .filter_map(|a| a.append_file.as_ref().map(|f| (a.name.clone(), f.clone())))
+        .collect::<HashMap<String, AppendFile>>();
+    if !append_files.is_empty() {
+        sinks.push(Box::new(AppendFileSink::new(append_files)));
+    }
+
+    sinks
+}
+
+#[cfg(feature = "audio")]
+fn sounds(config: &Config) -> HashMap<String, PathBuf> {
+    config
+        .actions
+        .iter()
+        .filter_map(|a| a.sound.as_ref().map(|s| (a.name.clone(), s.clone())))
+        .collect::<HashMap<String, PathBuf>>()
+}
+
+/// Applies every setting from the config that is not tied to a single action.
+fn apply_settings(listener: &mut HotkeyListener, config: &Config) {
+    // Settings missing from the config are reset, in case they were removed while running
+    listener.set_history_capacity(
+        config
+            .action_history_size
+            .unwrap_or(hotkey_listener::DEFAULT_HISTORY_CAPACITY),
+    );
+
+    if listener.timing() != &config.timing {
+        debug!("Using timing {:?}", config.timing);
+        listener.set_timing(config.timing.clone());
+    }
+
+    listener.set_hook_health_threshold(config.hook_health_threshold_secs.map(Duration::from_secs));
+
+    for (alias, key) in config.aliases.iter() {
+        if let Err(e) = listener.set_key_alias(alias, key) {
+            error!("Unable to create alias {}: {}", alias, e);
+        }
+    }
+
+    for (group_name, keys) in config.key_groups.iter() {
+        if let Err(e) = listener.set_key_group(group_name, keys) {
+            error!("Unable to create key group {}: {}", group_name, e);
+        }
+    }
+}
+
+/// Applies the per-action settings of an action from the config. Settings the action does not have are
+/// cleared, in case an earlier version of the action had them.
+fn apply_action_settings(listener: &mut HotkeyListener, action: &Action) {
+    listener.set_exclusive_group(&action.name, action.group_exclusive.as_deref());
+    listener.set_focus_filter(&action.name, action.focus_filter.as_deref());
+    listener.set_action_description(&action.name, action.description.as_deref());
+    listener.set_action_output_debounce(
+        &action.name,
+        action.output_debounce_ms.map(Duration::from_millis),
+    );
+}
+
+/// Registers an action from the config along with its per-action settings. Failures are logged.
+fn register_config_action(
+    listener: &mut HotkeyListener,
+    action: &Action,
+    sinks: &[Box<dyn ActionSink>],
+) {
+    apply_action_settings(listener, action);
+
+    if !action.enabled {
+        debug!("Skipping disabled action {}", action.name);
+        return;
+    }
+
+    let keys = match action.key_names() {
+        Ok(k) => k,
+        Err(e) => {
+            error!("Unable to register action {}: {}", action.name, e);
+            return;
+        }
+    };
+
+    if action.cooldown_ms.is_some()
+        && (action.taps.is_some() || action.ordered || action.window_ms.is_some())
+    {
+        error!("Ignoring cooldown_ms for action {}", action.name);
+    }
+    if !action.alternative_keys.is_empty()
+        && (action.taps.is_some()
+            || action.ordered
+            || action.window_ms.is_some()
+            || action.cooldown_ms.is_some())
+    {
+        error!("Ignoring alternative_keys for action {}", action.name);
+    }
+
+    let result = match (action.taps, action.ordered, action.window_ms) {
+        (Some(taps), _, window) => match keys.as_slice() {
+            [key] => listener.register_tap_action(
+                &action.name,
+                key,
+                taps,
+                Duration::from_millis(window.unwrap_or(DEFAULT_TAP_WINDOW_MS)),
+            ),
+            _ => {
+                error!("Tap action {} must have exactly one key", action.name);
+                return;
+            }
+        },
+        (None, true, window) => {
+            if window.is_some() {
+                error!("Ignoring window_ms for ordered action {}", action.name);
+            }
+            listener.register_sequence(&action.name, &keys)
+        }
+        (None, false, Some(ms)) => {
+            listener.register_action_with_window(&action.name, &keys, Duration::from_millis(ms))
+        }
+        (None, false, None) => match action.cooldown_ms {
+            Some(ms) => listener.register_action_with_cooldown(
+                &action.name,
+                &keys,
+                Duration::from_millis(ms),
+            ),
+            None if action.alternative_keys.is_empty() => {
+                listener.register_action(&action.name, &keys)
+            }
+            None => {
+                let mut combos = vec![keys];
+                combos.extend(action.alternative_keys.iter().cloned());
+                listener.register_action_aliases(&action.name, &combos)
+            }
+        },
+    };
+    if let Err(e) = result {
+        error!("Unable to register action {}: {}", action.name, e);
+        return;
+    }
+
+    #[cfg(feature = "audio")]
+    if action.sound.is_some() {
+        listener.mark_has_effect(&action.name);
+    }
+
+    if sinks.iter().any(|s| s.handles(&action.name)) {
+        listener.mark_has_effect(&action.name);
+    }
+}
+
+/// Undoes `register_config_action`, except for the per-action settings since another action can share the name.
+fn unregister_config_action(listener: &mut HotkeyListener, action: &Action) {
+    if !action.enabled {
+        return;
+    }
+
+    // Actions with bad keys were never registered
+    let keys = match action.key_names() {
+        Ok(k) => k,
+        Err(_) => return,
+    };
+
+    let result = match (action.taps, action.ordered) {
+        (Some(taps), _) => match keys.as_slice() {
+            [key] => listener.unregister_tap_action(
+                &action.name,
+                key,
+                taps,
+                Duration::from_millis(action.window_ms.unwrap_or(DEFAULT_TAP_WINDOW_MS)),
+            ),
+            _ => return,
+        },
+        (None, true) => listener.unregister_sequence(&action.name, &keys),
+        (None, false)
+            if action.alternative_keys.is_empty()
+                || action.window_ms.is_some()
+                || action.cooldown_ms.is_some() =>
+        {
+            listener.unregister_action(&action.name, &keys)
+        }
+        (None, false) => listener.unregister_action_all(&action.name),
+    };
+    if let Err(e) = result {
+        error!("Unable to unregister action {}: {}", action.name, e);
+    }
+}
+
+fn log_config_problems(config: &Config) {
+    if let Err(errors) = config.validate() {
+        error!("Found {} problems in the config:", errors.len());
+        for e in errors.iter() {
+            error!("  {}", e);
+        }
+    }
+}
+
+/// Applies the registrations queued by scripts. Failures are logged since the scripts have already moved on.
+fn apply_listener_commands(listener: &mut HotkeyListener, commands: &Receiver<ListenerCommand>) {
+    for command in commands.try_iter() {
+        let result = match &command {
+            ListenerCommand::Register { name, keys } => {
+                listener.register_action_from(name, keys, BindingSource::Script)
+            }
+            ListenerCommand::Unregister { name, keys } => listener.unregister_action(name, keys),
+            ListenerCommand::SetEnabled {
+                name,
+                keys,
+                enabled: true,
+            } => listener.enable_action(name, keys),
+            ListenerCommand::SetEnabled {
+                name,
+                keys,
+                enabled: false,
+            } => listener.disable_action(name, keys),
+        };
+        if let Err(e) = result {
+            error!("Unable to apply {:?} from a script: {}", command, e);
+        }
+    }
+}
+
+/// Unregisters every action registered by scripts, e.g. before the scripts are reloaded.
+fn unregister_script_actions(listener: &mut HotkeyListener) {
+    let mut by_source = listener.get_bindings_by_source();
+    let config_ids = by_source
+        .remove(&BindingSource::Config)
+        .unwrap_or_default()
+        .iter()
+        .map(|b| b.id)
+        .collect::<Vec<u64>>();
+    let bindings = by_source.remove(&BindingSource::Script).unwrap_or_default();
+    for binding in bindings.iter() {
+        // Key sequences only scripts use are removed whole, so their hooks are released in one go
+        if !config_ids.contains(&binding.id) {
+            if let Err(e) = listener.unregister_chord(binding.id) {
+                error!(
+                    "Unable to unregister script binding {:016x}: {}",
+                    binding.id, e
+                );
+            }
+            continue;
+        }
+
+        for action_name in binding.actions.iter() {
+            if let Err(e) = listener.unregister_action(action_name, &binding.keys) {
+                error!("Unable to unregister script action {}: {}", action_name, e);
+            }
+        }
+    }
+}
+
+/// Key names scripts can use besides the built-in ones.
+fn custom_key_names(config: &Config) -> Vec<&String> {
+    config
+        .aliases
+        .keys()
+        .chain(config.key_groups.keys())
+        .collect()
+}
+
+fn log_binding_problems(listener: &HotkeyListener) {
+    if !listener.hook_healthy() {
+        error!("The hotkey hook stopped working and could not be recreated, hotkeys will not fire");
+    }
+
+    for (first, second) in listener.find_conflicts() {
+        info!("Actions {} and {} have overlapping keys", first, second);
+    }
+
+    for action_name in listener.actions_without_effects() {
+        match listener.action_description(&action_name) {
+            Some(description) => info!(
+                "Action {} ({}) has nothing handling it",
+                action_name, description
+            ),
+            None => info!("Action {} has nothing handling it", action_name),
+        }
+    }
+}
+
+/// Waits for a chord to be pressed and prints its keys the way `keys` takes them, so they can be pasted
+/// into the config.
+fn capture(listener: &mut HotkeyListener) -> Result<(), Box<dyn Error>> {
+    let mut result = Ok(());
+    listener.with_elapsed_scope(CAPTURE_WINDOW, |listener| {
+        info!(
+            "Press the keys to capture within {}s",
+            CAPTURE_TIMEOUT.as_secs()
+        );
+        listener.start_capture();
+
+        let start = Instant::now();
+        while listener.is_capturing() {
+            if start.elapsed() > CAPTURE_TIMEOUT {
+                listener.stop_capture();
+                break;
+            }
+
+            if let Err(e) = listener.poll() {
+                result = Err(e);
+                return;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    });
+    result?;
+
+    match listener.take_capture() {
+        Some(keys) => println!("keys = {}", toml::Value::try_from(keys)?),
+        None => info!("No keys were pressed"),
+    }
+
+    Ok(())
+}
+
+/// Prints every key press and every fired action instead of running anything, to see what the hook receives.
+/// Runs until the process is stopped.
+fn monitor(
+    mut listener: HotkeyListener,
+    receiver: &Receiver<ActionEvent>,
+) -> Result<(), Box<dyn Error>> {
+    let (sender, presses) = unbounded::<KeyCode>();
+    listener.set_passthrough(Some(sender));
+    // Show every time an action fires, even the ones a debounce would hide
+    listener.set_output_debounce(Duration::ZERO);
+
+    for action_name in listener.get_action_names() {
+        listener.on(&action_name, |event| {
+            println!("{} fired by {}", event.name, event.keys.join(" + "))
+        });
+    }
+
+    info!("Printing key presses and actions, stop with Ctrl+C");
+    loop {
+        listener.poll()?;
+
+        for key in presses.try_iter() {
+            println!("{} pressed", hotkey_listener::key_name(key));
+        }
+        // The handlers already printed the actions
+        receiver.try_iter().for_each(drop);
+
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Applies a changed config to a running listener. Only actions that changed are unregistered and registered
+/// again, unless key groups or aliases changed since those can change what the keys of every action mean.
+fn reload_config(
+    listener: &mut HotkeyListener,
+    old: &Config,
+    new: &Config,
+    sinks: &[Box<dyn ActionSink>],
+) {
+    let everything = old.key_groups != new.key_groups || old.aliases != new.aliases;
+    let (mut removed, mut added) = if everything {
+        (
+            old.actions.iter().collect::<Vec<&Action>>(),
+            new.actions.iter().collect::<Vec<&Action>>(),
+        )
+    } else {
+        config_watcher::diff_actions(&old.actions, &new.actions)
+    };
+
+    // Actions that only changed their name keep their keys hooked, along with anything tied to the name
+    // like trigger counts
+    let mut renamed = 0;
+    if !everything {
+        for i in (0..removed.len()).rev() {
+            let from = removed[i];
+            let to = added.iter().position(|a| {
+                Action {
+                    name: from.name.clone(),
+                    ..(*a).clone()
+                } == *from
+                    && !old.actions.iter().any(|o| o.name == a.name)
+                    && !new.actions.iter().any(|n| n.name == from.name)
+            });
+
+            if let Some(j) = to {
+                match listener.rename_action(&from.name, &added[j].name) {
+                    Ok(_) => {
+                        debug!("Renamed action {} to {}", from.name, added[j].name);
+                        removed.remove(i);
+                        added.remove(j);
+                        renamed += 1;
+                    }
+                    // Disabled actions were never registered, they are handled like any other change
+                    Err(e) => debug!("Unable to rename action {}: {}", from.name, e),
+                }
+            }
+        }
+    }
+
+    // Scripts only register their actions once, so their bindings are registered again with the new key groups
+    let script_bindings = if everything {
+        listener
+            .get_bindings_by_source()
+            .remove(&BindingSource::Script)
+            .unwrap_or_default()
+    } else {
+        vec![]
+    };
+
+    if everything {
+        if let Err(e) = listener.clear() {
+            error!("Unable to remove every binding: {}", e);
+        }
+    }
+
+    // Unregister before applying the new key groups so the old keys still resolve the same way
+    for action in removed.iter() {
+        if !everything {
+            unregister_config_action(listener, action);
+        }
+
+        if !new.actions.iter().any(|a| a.name == action.name) {
+            listener.set_exclusive_group(&action.name, None);
+            listener.set_focus_filter(&action.name, None);
+            listener.set_action_output_debounce(&action.name, None);
+            listener.set_action_description(&action.name, None);
+            listener.off(&action.name);
+        }
+    }
+
+    apply_settings(listener, new);
+
+    for action in added.iter() {
+        register_config_action(listener, action, sinks);
     }
+
+    for binding in script_bindings.iter() {
+        for action_name in binding.actions.iter() {
+            if let Err(e) =
+                listener.register_action_from(action_name, &binding.keys, BindingSource::Script)
+            {
+                error!(
+                    "Unable to register script action {} again: {}",
+                    action_name, e
+                );
+            }
+        }
+    }
+
+    info!(
+        "Reloaded config, {} actions removed, {} added, and {} renamed",
+        removed.len(),
+        added.len(),
+        renamed
+    );
+
+    log_bindings(listener);
+    log_binding_problems(listener);
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -64,43 +1083,643 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     info!("Starting vaction: {} - {}", BUILD_NAME, GIT_REV);
 
+    let args = std::env::args().collect::<Vec<String>>();
+
     let dirs = ProjectDirs::from("com", "vpuppr", PROGRAM_NAME).unwrap();
 
     debug!("Config dir: {}", dirs.config_dir().display());
 
-    let config_dir = dirs.config_dir();
+    // An explicit config file also moves the scripts next to it, for portable setups
+    let config_override = match arg_value(&args, "--config") {
+        Some(path) => Some((PathBuf::from(path), "--config")),
+        None => std::env::var_os(CONFIG_ENV_VAR).map(|path| (PathBuf::from(path), CONFIG_ENV_VAR)),
+    };
+
+    let (config_dir, config_path, format) = match config_override {
+        Some((path, source)) => {
+            info!("Using config {} from {}", path.display(), source);
+
+            let dir = match path.parent() {
+                Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            let format = ConfigFormat::from_path(&path);
+
+            (dir, path, format)
+        }
+        None => {
+            let dir = dirs.config_dir().to_path_buf();
+
+            // config.json is only used if asked for or if it is the only config there is
+            let format = if args.iter().any(|a| a == "--json")
+                || (!dir.join(ConfigFormat::Toml.file_name()).exists()
+                    && dir.join(ConfigFormat::Json.file_name()).exists())
+            {
+                ConfigFormat::Json
+            } else {
+                ConfigFormat::Toml
+            };
+
+            (dir.clone(), dir.join(format.file_name()), format)
+        }
+    };
+    let config_dir = config_dir.as_path();
+
+    let mut scripts_dir = config_dir.to_path_buf();
+    scripts_dir.push("scripts");
+
+    if args.iter().any(|a| a == "--where") {
+        println!("Config directory:  {}", config_dir.display());
+        println!("Config file:       {}", config_path.display());
+        println!("Scripts directory: {}", scripts_dir.display());
+        println!("Data directory:    {}", dirs.data_dir().display());
+
+        return Ok(());
+    }
 
     if !config_dir.exists() {
         info!("Creating config directory {}", config_dir.display());
         std::fs::create_dir_all(config_dir)?;
     }
 
-    let mut config_path = config_dir.to_path_buf();
-    config_path.push("config.toml");
-
     let config_path = config_path.as_path();
     if !config_path.exists() {
         info!("Creating initial config {}", config_path.display());
 
-        let mut file = std::fs::File::create(config_path)?;
+        let config = match (format, args.iter().any(|a| a == "--no-defaults")) {
+            (_, true) => format.serialize(&Config::new())?,
+            // JSON has no comments, so there is nowhere to put the header
+            (ConfigFormat::Json, false) => format.serialize(&Config::with_defaults())?,
+            (ConfigFormat::Toml, false) => format!(
+                "{DEFAULT_CONFIG_HEADER}{}",
+                format.serialize(&Config::with_defaults())?
+            ),
+        };
+
+        utils::write_atomic(config_path, config.as_bytes())?;
+    }
+
+    #[cfg(feature = "bundle")]
+    {
+        if let Some(path) = arg_value(&args, "--export") {
+            let mut path = PathBuf::from(path);
+            if path.extension().is_none() {
+                path.set_extension(bundle::BUNDLE_EXTENSION);
+            }
+
+            bundle::export(config_dir, &path)?;
+            info!("Exported bundle to {}", path.display());
+
+            return Ok(());
+        }
+
+        if let Some(path) = arg_value(&args, "--import") {
+            let overwrite = args.iter().any(|a| a == "--overwrite");
+
+            bundle::import(config_dir, &PathBuf::from(path), overwrite)?;
+            info!("Imported bundle from {}", path);
+
+            return Ok(());
+        }
+    }
 
-        let config = toml::to_string_pretty(&Config::new())?;
+    // Always TOML, even for JSON configs, so the output can be pasted into a config.toml
+    if args.iter().any(|a| a == "--dump-effective") {
+        print!(
+            "{}",
+            toml::to_string_pretty(&effective_config(config_path)?)?
+        );
 
-        file.write_all(config.as_bytes())?;
+        return Ok(());
     }
 
     info!("Reading config from {}", config_path.display());
 
-    let config = std::fs::read_to_string(config_path)?;
+    let mut config = read_config(config_path)?;
 
-    debug!("{}", config);
+    log_config_problems(&config);
+
+    if let Err(e) = autostart::set_enabled(config.run_at_startup) {
+        error!("Unable to update starting on login: {}", e);
+    }
+
+    let (command_sender, mut command_receiver) = unbounded::<ListenerCommand>();
+    let mut lua = scripting::lua(dirs.data_dir(), command_sender, config.script_timeout())?;
+    scripting::set_custom_key_names(&lua, &custom_key_names(&config))?;
+
+    if scripts_dir.exists() {
+        info!("Loading scripts from {}", scripts_dir.display());
+        // Failed scripts are logged and skipped
+        if let Err(e) = scripting::load_scripts(&lua, &scripts_dir) {
+            error!("Unable to load scripts: {}", e);
+        }
+    }
+
+    if args.iter().any(|a| a == "--repl") {
+        scripting::repl(&lua)?;
+
+        return Ok(());
+    }
+
+    let mut sinks = create_sinks(&config);
+
+    if let Some(ms) = config.startup_delay_ms {
+        info!("Waiting {}ms before creating hotkey hook", ms);
+        std::thread::sleep(Duration::from_millis(ms));
+    }
+
+    let (sender, receiver) = unbounded::<ActionEvent>();
 
-    // TODO testing
+    // livesplit-hotkey reads /dev/input through evdev on Linux, so X11 and Wayland sessions both work
+    // as long as the input devices are readable. Unreadable devices are skipped silently by the hook.
+    #[cfg(target_os = "linux")]
     {
-        let lua = scripting::lua()?;
+        info!(
+            "Reading keys from /dev/input for {} session",
+            std::env::var("XDG_SESSION_TYPE").unwrap_or_else(|_| "unknown".to_string())
+        );
 
-        lua.load(include_str!("test.lua")).exec()?;
+        let readable = std::fs::read_dir("/dev/input")
+            .map(|entries| {
+                entries.filter_map(|e| e.ok()).any(|e| {
+                    e.file_name().to_string_lossy().starts_with("event")
+                        && std::fs::File::open(e.path()).is_ok()
+                })
+            })
+            .unwrap_or(false);
+        if !readable {
+            error!("No readable devices in /dev/input, hotkeys will not work. Is the user in the input group?");
+        }
+    }
+
+    let mut listener = HotkeyListener::new(sender)?;
+
+    apply_settings(&mut listener, &config);
+
+    if args.iter().any(|a| a == "--capture") {
+        return capture(&mut listener);
+    }
+
+    // The listener resumes itself when the toggle fires while paused, pausing is done by the main loop
+    listener.set_resume_action(Some(TOGGLE_PAUSE_ACTION));
+    listener.mark_has_effect(TOGGLE_PAUSE_ACTION);
+    let mut paused = false;
+
+    // Scripts are loaded once, so their callbacks still count after a config reload
+    for action_name in scripting::action_callback_names(&lua)? {
+        listener.mark_has_effect(&action_name);
+    }
+
+    for action in config.actions.iter() {
+        register_config_action(&mut listener, action, &sinks);
+    }
+
+    apply_listener_commands(&mut listener, &command_receiver);
+
+    log_bindings(&listener);
+    log_binding_problems(&listener);
+
+    if args.iter().any(|a| a == "--monitor") {
+        return monitor(listener, &receiver);
+    }
+
+    #[cfg(feature = "audio")]
+    let mut sounds = sounds(&config);
+    #[cfg(feature = "audio")]
+    let mut sound_player = if sounds.is_empty() {
+        None
+    } else {
+        Some(audio::SoundPlayer::new())
+    };
+
+    let mut watcher = ConfigWatcher::new(config_path);
+    let mut script_watcher = DirWatcher::new(&scripts_dir, "lua");
+    let mut handle = listener.spawn(POLL_INTERVAL);
+
+    // Checked on a schedule instead of only when no actions fire, so a steady stream of actions
+    // cannot hold off reloads
+    let mut last_check = Instant::now();
+
+    loop {
+        let event = match receiver
+            .recv_timeout(CONFIG_CHECK_INTERVAL.saturating_sub(last_check.elapsed()))
+        {
+            Ok(event) => Some(event),
+            Err(RecvTimeoutError::Timeout) => None,
+            // The listener thread exited and dropped its sender
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        if last_check.elapsed() >= CONFIG_CHECK_INTERVAL {
+            last_check = Instant::now();
+
+            // Checked directly since the channel only disconnects once every clone of its sender is dropped
+            if handle.is_finished() {
+                break;
+            }
+
+            if script_watcher.changed() {
+                // A script with errors, e.g. one saved halfway through editing, leaves the previous scripts running
+                match scripting::reload(&lua, &scripts_dir) {
+                    Ok((new_lua, new_receiver)) => {
+                        let mut listener = match handle.stop() {
+                            Ok(l) => l,
+                            Err(e) => {
+                                error!("Stopping: {}", e);
+                                return Ok(());
+                            }
+                        };
+
+                        unregister_script_actions(&mut listener);
+
+                        lua = new_lua;
+                        command_receiver = new_receiver;
+                        for action_name in scripting::action_callback_names(&lua)? {
+                            listener.mark_has_effect(&action_name);
+                        }
+                        apply_listener_commands(&mut listener, &command_receiver);
+
+                        info!("Reloaded scripts from {}", scripts_dir.display());
+                        log_bindings(&listener);
+                        log_binding_problems(&listener);
+
+                        handle = listener.spawn(POLL_INTERVAL);
+                    }
+                    Err(e) => {
+                        error!("Unable to reload scripts, keeping the previous ones: {}", e)
+                    }
+                }
+            }
+
+            // Half-saved files are expected while editing, the next save triggers another reload
+            if watcher.changed() {
+                match read_config(config_path) {
+                    Ok(new_config) => {
+                        log_config_problems(&new_config);
+
+                        let mut listener = match handle.stop() {
+                            Ok(l) => l,
+                            Err(e) => {
+                                error!("Stopping: {}", e);
+                                return Ok(());
+                            }
+                        };
+
+                        if new_config.fifo_sink != config.fifo_sink
+                            || new_config
+                                .actions
+                                .iter()
+                                .map(|a| &a.append_file)
+                                .ne(config.actions.iter().map(|a| &a.append_file))
+                        {
+                            sinks = create_sinks(&new_config);
+                        }
+
+                        reload_config(&mut listener, &config, &new_config, &sinks);
+                        scripting::set_time_limit(&lua, new_config.script_timeout());
+                        if let Err(e) =
+                            scripting::set_custom_key_names(&lua, &custom_key_names(&new_config))
+                        {
+                            error!("Unable to update key names for scripts: {}", e);
+                        }
+
+                        #[cfg(feature = "audio")]
+                        {
+                            sounds = self::sounds(&new_config);
+                            if sound_player.is_none() && !sounds.is_empty() {
+                                sound_player = Some(audio::SoundPlayer::new());
+                            }
+                        }
+
+                        config = new_config;
+                        handle = listener.spawn(POLL_INTERVAL);
+                    }
+                    Err(e) => error!("Unable to reload config, keeping previous bindings: {}", e),
+                }
+            }
+        }
+
+        let event = match event {
+            Some(event) => event,
+            None => continue,
+        };
+
+        // How long the event waited in the channel, which grows if handling actions falls behind
+        debug!(
+            "Action fired: {} ({}ms ago)",
+            event.to_json(),
+            event.at.elapsed().as_millis()
+        );
+
+        if event.name == TOGGLE_PAUSE_ACTION {
+            let mut listener = match handle.stop() {
+                Ok(l) => l,
+                Err(e) => {
+                    error!("Stopping: {}", e);
+                    return Ok(());
+                }
+            };
+
+            // A toggle while paused has already resumed the listener
+            if !paused {
+                listener.pause();
+            }
+            paused = listener.is_paused();
+
+            handle = listener.spawn(POLL_INTERVAL);
+
+            info!("{} hotkeys", if paused { "Paused" } else { "Resumed" });
+        }
+
+        #[cfg(feature = "audio")]
+        if let (Some(player), Some(path)) = (sound_player.as_ref(), sounds.get(&event.name)) {
+            player.play(path);
+        }
+
+        for sink in sinks.iter_mut() {
+            if let Err(e) = sink.send(&event.name) {
+                error!("Unable to send {} to sink: {:?}", event.name, e);
+            }
+        }
+
+        if let Err(e) = scripting::dispatch(&lua, &event) {
+            error!("Script handling {} failed: {}", event.name, e);
+        }
+
+        if !command_receiver.is_empty() {
+            let mut listener = match handle.stop() {
+                Ok(l) => l,
+                Err(e) => {
+                    error!("Stopping: {}", e);
+                    return Ok(());
+                }
+            };
+
+            apply_listener_commands(&mut listener, &command_receiver);
+
+            handle = listener.spawn(POLL_INTERVAL);
+        }
+    }
+
+    if let Err(e) = handle.join() {
+        error!("Stopping: {}", e);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::test_dir;
+
+    /// A config with every setting filled in, including the tables that have to come after scalars in TOML.
+    fn full_config() -> Config {
+        let mut config = Config::with_defaults();
+        config.startup_delay_ms = Some(500);
+        config.fifo_sink = Some(PathBuf::from("/tmp/viraction.fifo"));
+        config.action_history_size = Some(10);
+        config.hook_health_threshold_secs = Some(30);
+        config.script_timeout_ms = Some(100);
+        config.actions.push(Action {
+            name: "wave".to_string(),
+            description: Some("Waves".to_string()),
+            keys: vec!["ControlLeft".to_string(), "KeyW".to_string()],
+            alternative_keys: vec![vec!["F13".to_string()]],
+            window_ms: Some(50),
+            group_exclusive: Some("gestures".to_string()),
+            output_debounce_ms: Some(20),
+            cooldown_ms: Some(1000),
+            focus_filter: Some("obs".to_string()),
+            append_file: Some(AppendFile {
+                path: PathBuf::from("waves.log"),
+                template: "{time} {action}".to_string(),
+            }),
+            ..Default::default()
+        });
+        config.key_groups.insert(
+            "AnyEnter".to_string(),
+            vec!["Enter".to_string(), "NumpadEnter".to_string()],
+        );
+        config
+            .aliases
+            .insert("MyMacroKey".to_string(), "F13".to_string());
+        config
+    }
+
+    #[test]
+    fn changed_key_groups_register_every_binding_again() {
+        let (sender, _receiver) = unbounded();
+        let mut listener = HotkeyListener::new(sender).unwrap();
+        let mut old = Config::new();
+        old.key_groups
+            .insert("Trigger".to_string(), vec!["KeyA".to_string()]);
+        old.actions.push(Action {
+            name: "wave".to_string(),
+            keys: vec!["Trigger".to_string()],
+            ..Default::default()
+        });
+        apply_settings(&mut listener, &old);
+        register_config_action(&mut listener, &old.actions[0], &[]);
+        listener
+            .register_action_from(
+                &"script".to_string(),
+                &["Trigger".to_string(), "KeyB".to_string()],
+                BindingSource::Script,
+            )
+            .unwrap();
+
+        let mut new = old.clone();
+        new.key_groups
+            .insert("Trigger".to_string(), vec!["KeyC".to_string()]);
+        reload_config(&mut listener, &old, &new, &[]);
+
+        let press = |key: &str| listener.would_fire(&[(key.to_string(), Duration::ZERO)]);
+        assert!(press("KeyA").unwrap().is_empty());
+        assert_eq!(press("KeyC").unwrap(), ["wave"]);
+        assert_eq!(listener.get_action_names(), ["script", "wave"]);
+        assert_eq!(
+            listener.get_bindings_by_source()[&BindingSource::Script][0].actions,
+            ["script"]
+        );
+    }
+
+    #[test]
+    fn alternative_keys_fire_the_action_and_are_removed_with_it() {
+        let (sender, _receiver) = unbounded();
+        let mut listener = HotkeyListener::new(sender).unwrap();
+        let action = Action {
+            name: "mic".to_string(),
+            keys: vec!["F13".to_string()],
+            alternative_keys: vec![vec!["ControlLeft".to_string(), "KeyM".to_string()]],
+            ..Default::default()
+        };
+
+        register_config_action(&mut listener, &action, &[]);
+        let mut keys = listener.keys_for_action("mic");
+        keys.sort();
+        assert_eq!(keys, [vec!["ControlLeft", "KeyM"], vec!["F13"]]);
+
+        unregister_config_action(&mut listener, &action);
+        assert!(listener.get_action_names().is_empty());
+        assert!(listener.get_key_names().is_empty());
+    }
+
+    #[test]
+    fn renamed_actions_are_renamed_in_place() {
+        let (sender, _receiver) = unbounded();
+        let mut listener = HotkeyListener::new(sender).unwrap();
+        let mut old = Config::new();
+        old.actions.push(Action {
+            name: "wave".to_string(),
+            keys: vec!["KeyW".to_string()],
+            group_exclusive: Some("gestures".to_string()),
+            ..Default::default()
+        });
+        register_config_action(&mut listener, &old.actions[0], &[]);
+        listener.set_output_debounce(Duration::ZERO);
+        listener.reset_counts();
+
+        let mut new = old.clone();
+        new.actions[0].name = "hello".to_string();
+        reload_config(&mut listener, &old, &new, &[]);
+
+        assert_eq!(listener.get_action_names(), ["hello"]);
+        assert_eq!(
+            listener
+                .would_fire(&[("KeyW".to_string(), Duration::ZERO)])
+                .unwrap(),
+            ["hello"]
+        );
+
+        // Anything else changing as well is a removal and an addition
+        let mut newer = new.clone();
+        newer.actions[0].name = "bye".to_string();
+        newer.actions[0].keys = vec!["KeyB".to_string()];
+        reload_config(&mut listener, &new, &newer, &[]);
+        assert_eq!(listener.keys_for_action("bye"), [vec!["KeyB"]]);
+        assert!(listener.keys_for_action("hello").is_empty());
+    }
+
+    #[test]
+    fn validate_reports_every_problem() {
+        let action = |name: &str, keys: &[&str]| Action {
+            name: name.to_string(),
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut config = Config::new();
+        config.actions = vec![
+            action("", &["KeyA"]),
+            action("wave", &["KeyB"]),
+            action("wave", &["KeyC"]),
+            action("jump", &["KeyB"]),
+            action("typo", &["KeyNope"]),
+            Action {
+                keycodes: vec![30],
+                ..action("both", &["KeyD"])
+            },
+            Action {
+                name: "unknown".to_string(),
+                keycodes: vec![u32::MAX],
+                ..Default::default()
+            },
+        ];
+
+        let errors = config
+            .validate()
+            .unwrap_err()
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                "action #1 has no name".to_string(),
+                "action wave is defined more than once".to_string(),
+                "actions wave and jump have the same keys".to_string(),
+                "action typo: unknown key 'KeyNope'".to_string(),
+                "both uses both keys and keycodes".to_string(),
+                format!("unknown uses invalid keycode {}", u32::MAX),
+            ]
+        );
+    }
+
+    #[test]
+    fn config_survives_save_and_read() {
+        let dir = test_dir("round-trip");
+        let path = dir.join(ConfigFormat::Toml.file_name());
+        let config = full_config();
+
+        config.save(&path).unwrap();
+        assert_eq!(read_config(&path).unwrap(), config);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn config_survives_save_and_read_as_json() {
+        let dir = test_dir("round-trip-json");
+        let path = dir.join(ConfigFormat::Json.file_name());
+        let config = full_config();
+
+        config.save(&path).unwrap();
+        assert_eq!(read_config(&path).unwrap(), config);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn effective_config_does_not_rewrite_old_configs() {
+        let dir = test_dir("effective");
+        let path = dir.join(ConfigFormat::Toml.file_name());
+        let old = "run_at_startup = false\noutput_debounce_ms = 50\nactions = []\n";
+        std::fs::write(&path, old).unwrap();
+
+        let config = effective_config(&path).unwrap();
+        assert_eq!(config.version, migrate::CONFIG_VERSION);
+        assert_eq!(config.timing.output_debounce, Duration::from_millis(50));
+
+        // No upgraded file and no backup
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), old);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn formats_parse_to_the_same_config() {
+        let toml = r#"
+            version = 1
+            run_at_startup = true
+
+            [[actions]]
+            name = "wave"
+            keys = ["ControlLeft", "KeyW"]
+        "#;
+        let json = r#"{
+            "version": 1,
+            "run_at_startup": true,
+            "fifo_sink": null,
+            "actions": [{ "name": "wave", "keys": ["ControlLeft", "KeyW"] }]
+        }"#;
+
+        let (from_toml, _) = parse_config(toml, ConfigFormat::Toml).unwrap();
+        let (from_json, _) = parse_config(json, ConfigFormat::Json).unwrap();
+        assert_eq!(from_toml, from_json);
+        assert_eq!(from_json.actions[0].name, "wave");
+    }
+
+    #[test]
+    fn json_integers_too_large_for_toml_are_rejected() {
+        let json = format!(r#"{{ "version": 1, "startup_delay_ms": {} }}"#, u64::MAX);
+
+        let error = parse_config(&json, ConfigFormat::Json).unwrap_err();
+        assert!(error.to_string().contains("too large"), "{error}");
+
+        let value = json_to_toml(serde_json::json!(i64::MAX)).unwrap();
+        assert_eq!(value, Some(toml::Value::Integer(i64::MAX)));
+        let value = json_to_toml(serde_json::json!(1.5)).unwrap();
+        assert_eq!(value, Some(toml::Value::Float(1.5)));
+    }
+}
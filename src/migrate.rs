@@ -0,0 +1,65 @@
+//! Upgrades config files written by older versions of viraction to the current shape.
+
+use log::warn;
+use toml::{value::Table, Value};
+
+/// Version of the config format written by this build. Bump this and add a step to `migrate` whenever
+/// a change would break existing config files.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Upgrades a parsed config to `CONFIG_VERSION`. Configs without a `version` predate versioning and are
+/// treated as version 0.
+///
+/// Returns whether anything changed, i.e. whether the file should be rewritten.
+pub fn migrate(config: &mut Value) -> bool {
+    let table = match config.as_table_mut() {
+        Some(t) => t,
+        None => return false,
+    };
+
+    let version = table
+        .get("version")
+        .and_then(|v| v.as_integer())
+        .unwrap_or(0);
+    if version > CONFIG_VERSION as i64 {
+        warn!(
+            "Config version {} is newer than the supported version {}, some settings may be ignored",
+            version, CONFIG_VERSION
+        );
+        return false;
+    }
+    if version == CONFIG_VERSION as i64 {
+        return false;
+    }
+
+    if version < 1 {
+        v0_to_v1(table);
+    }
+
+    table.insert("version".to_string(), Value::Integer(CONFIG_VERSION as i64));
+
+    true
+}
+
+/// Moves the top-level timing settings into the `[timing]` table.
+fn v0_to_v1(config: &mut Table) {
+    let mut moved = Table::new();
+    for key in ["output_debounce_ms", "sequential_grace_ms"] {
+        if let Some(value) = config.remove(key) {
+            moved.insert(key.to_string(), value);
+        }
+    }
+    if moved.is_empty() {
+        return;
+    }
+
+    if let Value::Table(timing) = config
+        .entry("timing".to_string())
+        .or_insert_with(|| Value::Table(Table::new()))
+    {
+        for (key, value) in moved {
+            // Keep whatever was already set in the new location
+            timing.entry(key).or_insert(value);
+        }
+    }
+}
@@ -3,10 +3,33 @@ use crate::utils::feature_gate;
 feature_gate!(
     feature: "lua",
     mods: { lua, },
-    uses: { lua::lua, }
+    uses: {
+        lua::lua, lua::load_scripts, lua::repl, lua::dispatch, lua::action_callback_names,
+        lua::set_custom_key_names, lua::set_time_limit, lua::reload,
+    }
 
 );
 
+/// A change to the hotkey listener requested by a script. The listener runs on its own thread, so scripts
+/// queue these and the main loop applies them.
+#[derive(Debug, Clone)]
+pub enum ListenerCommand {
+    Register {
+        name: String,
+        keys: Vec<String>,
+    },
+    Unregister {
+        name: String,
+        keys: Vec<String>,
+    },
+    /// Enables or disables any registered action, including ones from the config.
+    SetEnabled {
+        name: String,
+        keys: Vec<String>,
+        enabled: bool,
+    },
+}
+
 pub(crate) mod types {
     macro_rules! primitive {
         ($($name:ident$(($type:ty))?),+) => {
@@ -54,6 +77,12 @@ pub(crate) mod types {
 }
 
 pub(crate) mod utils {
+    /// An HTTP client for scripts. Clones share the same connection pool.
     #[derive(Debug, Clone)]
-    pub struct Request;
+    pub struct Request {
+        #[cfg(feature = "reqwest")]
+        pub client: reqwest::blocking::Client,
+        /// How long a request may take, including reading the body.
+        pub timeout: std::time::Duration,
+    }
 }
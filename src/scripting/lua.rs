@@ -1,8 +1,36 @@
-use std::error::Error;
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    error::Error,
+    io::{BufRead, Write},
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
+use crossbeam_channel::{unbounded, Receiver, Sender};
 use mlua::prelude::*;
 
-use super::{types::*, utils::*};
+use super::{types::*, utils::*, ListenerCommand};
+use crate::hotkey_listener::{self, ActionEvent, Modifier};
+
+/// Name of the optional global function scripts can define to run once every script is loaded.
+const INIT_FUNCTION: &str = "init";
+
+/// Registry key of the table mapping action names to the functions registered with `on_action`.
+const ACTION_CALLBACKS: &str = "viraction_action_callbacks";
+
+/// Registry key of the name of the action whose `on_action` function is running, if any.
+const CURRENT_ACTION: &str = "viraction_current_action";
+
+/// How long script HTTP requests may take unless the script sets its own timeout.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Tables nested deeper than this when encoding JSON are assumed to contain themselves.
+const MAX_JSON_DEPTH: usize = 128;
+
+/// Registry key of the table of key names defined outside of scripts, i.e. config aliases and key groups.
+const CUSTOM_KEY_NAMES: &str = "viraction_custom_key_names";
 
 /// Quickly implement the `FromLua` trait.
 macro_rules! impl_from_lua {
@@ -107,31 +135,1214 @@ multi_impl_from_lua!(
     }
 );
 
+/// Sends a request with the headers from `headers`, a table of header names to values, or `nil`.
+///
+/// The result is a table with `ok` set for 2xx responses, the `status` and `body` of the response if there
+/// was one, and an `error` message if the request could not be sent or the body could not be read.
+/// `timed_out` is set if that was because the request took too long.
+#[cfg(feature = "reqwest")]
+fn send_request<'lua>(
+    lua: &'lua Lua,
+    mut request: reqwest::blocking::RequestBuilder,
+    headers: LuaValue,
+) -> LuaResult<LuaTable<'lua>> {
+    match header_args(lua, headers) {
+        Ok(headers) => {
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+        }
+        Err(e) => return failed_request(lua, e),
+    }
+
+    let result = lua.create_table()?;
+    match request.send() {
+        Ok(response) => {
+            result.set("ok", response.status().is_success())?;
+            result.set("status", response.status().as_u16())?;
+            match response.text() {
+                Ok(body) => result.set("body", body)?,
+                Err(e) => {
+                    result.set("timed_out", e.is_timeout())?;
+                    result.set("error", e.to_string())?;
+                }
+            }
+        }
+        Err(e) => {
+            result.set("ok", false)?;
+            result.set("timed_out", e.is_timeout())?;
+            result.set("error", e.to_string())?;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Converts the headers argument of a request, like `string_arg` does for strings.
+#[cfg(feature = "reqwest")]
+fn header_args<'lua>(lua: &'lua Lua, headers: LuaValue<'lua>) -> Result<Vec<(String, String)>, String> {
+    let headers = match headers {
+        LuaValue::Table(t) => t,
+        LuaValue::Nil => return Ok(vec![]),
+        _ => return Err("headers must be a table".to_string()),
+    };
+
+    headers
+        .pairs::<LuaValue, LuaValue>()
+        .map(|pair| {
+            let (name, value) = pair.map_err(|e| e.to_string())?;
+            Ok((string_arg(lua, name, "header name")?, string_arg(lua, value, "header value")?))
+        })
+        .collect()
+}
+
+/// The result of a request that was never sent, in the same form `send_request` returns.
+#[cfg(feature = "reqwest")]
+fn failed_request(lua: &Lua, error: String) -> LuaResult<LuaTable<'_>> {
+    let result = lua.create_table()?;
+    result.set("ok", false)?;
+    result.set("timed_out", false)?;
+    result.set("error", error)?;
+    Ok(result)
+}
+
+/// Quickly implement the `ToLua` trait, the reverse of `multi_impl_from_lua!`.
+macro_rules! multi_impl_to_lua {
+    (
+        $($type:ty {
+            ($v:pat, $l:ident) => $r:expr
+        })+
+    ) => {
+        $(
+            impl<'lua> ToLua<'lua> for $type {
+                fn to_lua(self, $l: &'lua Lua) -> mlua::Result<mlua::Value<'lua>> {
+                    let $v = self;
+                    $r
+                }
+            }
+        )+
+    };
+}
+
+// Luau integers are only 32 bits, so wider integers that do not fit become numbers
+multi_impl_to_lua!(
+    ScriptNull {
+        (_, _lua) => Ok(LuaValue::Nil)
+    }
+
+    ScriptString {
+        (ScriptString(s), lua) => Ok(LuaValue::String(lua.create_string(&s)?))
+    }
+
+    ScriptBool {
+        (ScriptBool(b), _lua) => Ok(LuaValue::Boolean(b))
+    }
+
+    ScriptU8 {
+        (ScriptU8(i), _lua) => Ok(LuaValue::Integer(i as LuaInteger))
+    }
+    ScriptI8 {
+        (ScriptI8(i), _lua) => Ok(LuaValue::Integer(i as LuaInteger))
+    }
+    ScriptU16 {
+        (ScriptU16(i), _lua) => Ok(LuaValue::Integer(i as LuaInteger))
+    }
+    ScriptI16 {
+        (ScriptI16(i), _lua) => Ok(LuaValue::Integer(i as LuaInteger))
+    }
+    ScriptU32 {
+        (ScriptU32(i), _lua) => Ok(LuaInteger::try_from(i).map_or(LuaValue::Number(i as f64), LuaValue::Integer))
+    }
+    ScriptI32 {
+        (ScriptI32(i), _lua) => Ok(LuaValue::Integer(i))
+    }
+    ScriptU64 {
+        (ScriptU64(i), _lua) => Ok(LuaInteger::try_from(i).map_or(LuaValue::Number(i as f64), LuaValue::Integer))
+    }
+    ScriptI64 {
+        (ScriptI64(i), _lua) => Ok(LuaInteger::try_from(i).map_or(LuaValue::Number(i as f64), LuaValue::Integer))
+    }
+
+    ScriptF32 {
+        (ScriptF32(f), _lua) => Ok(LuaValue::Number(f as f64))
+    }
+    ScriptF64 {
+        (ScriptF64(f), _lua) => Ok(LuaValue::Number(f))
+    }
+);
+
+/// Reads a sequence. Elements after the first `nil` are ignored.
+impl<'lua, T: FromLua<'lua>> FromLua<'lua> for ScriptList<Vec<T>> {
+    fn from_lua(lua_value: mlua::Value<'lua>, _: &'lua Lua) -> mlua::Result<Self> {
+        match lua_value {
+            LuaValue::Table(t) => Ok(ScriptList(
+                t.sequence_values::<T>().collect::<LuaResult<Vec<T>>>()?,
+            )),
+            _ => Err(LuaError::UserDataTypeMismatch),
+        }
+    }
+}
+
+impl<'lua, T: ToLua<'lua>> ToLua<'lua> for ScriptList<Vec<T>> {
+    fn to_lua(self, lua: &'lua Lua) -> mlua::Result<mlua::Value<'lua>> {
+        Ok(LuaValue::Table(lua.create_sequence_from(self.0)?))
+    }
+}
+
+/// Reads every key/value pair. Number keys are converted to strings.
+impl<'lua, T: FromLua<'lua>> FromLua<'lua> for ScriptDictionary<HashMap<String, T>> {
+    fn from_lua(lua_value: mlua::Value<'lua>, _: &'lua Lua) -> mlua::Result<Self> {
+        match lua_value {
+            LuaValue::Table(t) => Ok(ScriptDictionary(
+                t.pairs::<String, T>()
+                    .collect::<LuaResult<HashMap<String, T>>>()?,
+            )),
+            _ => Err(LuaError::UserDataTypeMismatch),
+        }
+    }
+}
+
+impl<'lua, T: ToLua<'lua>> ToLua<'lua> for ScriptDictionary<HashMap<String, T>> {
+    fn to_lua(self, lua: &'lua Lua) -> mlua::Result<mlua::Value<'lua>> {
+        Ok(LuaValue::Table(lua.create_table_from(self.0)?))
+    }
+}
+
 impl mlua::UserData for Request {
     fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
         #[cfg(feature = "reqwest")]
-        methods.add_method::<_, ScriptString, _, _>("get", |_, _, url| {
-            let res = match reqwest::blocking::get(url.0) {
-                Ok(a) => a.text().unwrap_or_default(),
-                Err(e) => format!("{}", e),
-            };
-
-            Ok(res)
+        methods.add_method::<_, (LuaValue, LuaValue), _, _>("get", |lua, this, (url, headers)| {
+            match string_arg(lua, url, "url") {
+                Ok(url) => send_request(lua, this.client.get(url).timeout(this.timeout), headers),
+                Err(e) => failed_request(lua, e),
+            }
         });
+
+        #[cfg(feature = "reqwest")]
+        methods.add_method::<_, (LuaValue, LuaValue, LuaValue), _, _>(
+            "post",
+            |lua, this, (url, body, headers)| match (string_arg(lua, url, "url"), string_arg(lua, body, "body")) {
+                (Ok(url), Ok(body)) => {
+                    let request = this.client.post(url).body(body);
+                    send_request(lua, request.timeout(this.timeout), headers)
+                }
+                (Err(e), _) | (_, Err(e)) => failed_request(lua, e),
+            },
+        );
+    }
+}
+
+/// The directory passed to `lua()`, kept in the state's app data so `reload` can create an identical state.
+struct DataDir(PathBuf);
+
+/// How long scripts may run each time they are called into, shared between the `Lua` state's app data and its
+/// interrupt callback.
+struct TimeLimit {
+    limit: Cell<Duration>,
+    /// When the running script has to stop. Unset while no script is running.
+    deadline: Cell<Option<Instant>>,
+}
+
+/// Calls `f` with the time limit set by `lua()`, returning an error if it runs for too long.
+///
+/// Luau aborts the process when the interrupt callback returns an error, so `f` runs in its own coroutine and
+/// the interrupt yields it instead once the deadline passes. The coroutine is then never resumed. Loops inside
+/// metamethods cannot yield and are not stopped.
+fn call_with_time_limit<'lua, A, R>(lua: &'lua Lua, f: LuaFunction<'lua>, args: A) -> LuaResult<R>
+where
+    A: ToLuaMulti<'lua>,
+    R: FromLuaMulti<'lua>,
+{
+    let time_limit = match lua.app_data_ref::<Rc<TimeLimit>>() {
+        Some(t) => t.clone(),
+        None => return f.call(args),
+    };
+
+    // Nested calls share the deadline of the outermost call
+    let previous = time_limit.deadline.get();
+    if previous.is_none() {
+        time_limit
+            .deadline
+            .set(Some(Instant::now() + time_limit.limit.get()));
+    }
+    let thread = lua.create_thread(f)?;
+    let result = thread.resume(args);
+    time_limit.deadline.set(previous);
+
+    if thread.status() == LuaThreadStatus::Resumable {
+        return Err(LuaError::RuntimeError(format!(
+            "script ran for longer than {}ms",
+            time_limit.limit.get().as_millis()
+        )));
+    }
+
+    result
+}
+
+/// Wraps a function returning `(ok, result)` so that failures are raised as Lua errors, with `result` as the
+/// message. On success `result` is returned. Luau aborts the process when a Rust callback returns an error, so
+/// callbacks that can fail return the message instead.
+fn raise_on_failure<'lua>(lua: &'lua Lua, f: LuaFunction<'lua>) -> LuaResult<LuaFunction<'lua>> {
+    lua.load(
+        "local f = ...
+        return function(...)
+            local ok, result = f(...)
+            if not ok then
+                error(result, 2)
+            end
+            return result
+        end",
+    )
+    .set_name("raise_on_failure")?
+    .call(f)
+}
+
+/// Converts a string argument of a script function. Arguments are converted by hand since a failed conversion
+/// in mlua would abort the process.
+fn string_arg<'lua>(lua: &'lua Lua, value: LuaValue<'lua>, name: &str) -> Result<String, String> {
+    match value {
+        LuaValue::String(_) => ScriptString::from_lua(value, lua)
+            .map(|s| s.0)
+            .map_err(|_| format!("{name} must be valid UTF-8")),
+        _ => Err(format!("{name} must be a string")),
+    }
+}
+
+/// Joins the arguments of a `log` function with tabs, like `print` does.
+fn log_message(lua: &Lua, args: LuaMultiValue) -> String {
+    let to_string = lua.globals().get::<_, LuaFunction>("tostring");
+
+    args.into_iter()
+        .map(|value| match (&value, &to_string) {
+            (LuaValue::String(s), _) => s.to_string_lossy().to_string(),
+            (_, Ok(f)) => f
+                .call::<_, String>(value.clone())
+                .unwrap_or_else(|_| value.type_name().to_string()),
+            (_, Err(_)) => value.type_name().to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join("\t")
+}
+
+/// Converts a Lua value to JSON. Sequences become arrays and other tables become objects, so an empty table is
+/// encoded as `{}`. Functions and other values without a JSON equivalent are errors.
+fn lua_to_json(value: LuaValue, depth: usize) -> Result<serde_json::Value, String> {
+    use serde_json::Value;
+
+    if depth > MAX_JSON_DEPTH {
+        return Err("tables are nested too deeply, or contain themselves".to_string());
+    }
+
+    Ok(match value {
+        LuaValue::Nil => Value::Null,
+        LuaValue::Boolean(b) => Value::Bool(b),
+        LuaValue::Integer(i) => Value::from(i),
+        // Whole numbers too large for a Luau integer are still written without a fraction
+        LuaValue::Number(n) if n.fract() == 0.0 && n.abs() < 2f64.powi(53) => Value::from(n as i64),
+        LuaValue::Number(n) => serde_json::Number::from_f64(n)
+            .map(Value::Number)
+            .ok_or_else(|| format!("{n} cannot be encoded as JSON"))?,
+        LuaValue::String(s) => Value::String(s.to_string_lossy().to_string()),
+        LuaValue::Table(t) => {
+            let len = t.raw_len().max(0) as usize;
+            let pairs = t
+                .pairs::<LuaValue, LuaValue>()
+                .collect::<LuaResult<Vec<(LuaValue, LuaValue)>>>()
+                .map_err(|e| e.to_string())?;
+
+            let is_sequence = len > 0
+                && pairs.len() == len
+                && pairs
+                    .iter()
+                    .all(|(key, _)| matches!(key, LuaValue::Integer(i) if *i >= 1 && *i as usize <= len));
+            if is_sequence {
+                let mut array = vec![Value::Null; len];
+                for (key, value) in pairs {
+                    if let LuaValue::Integer(i) = key {
+                        array[i as usize - 1] = lua_to_json(value, depth + 1)?;
+                    }
+                }
+                Value::Array(array)
+            } else {
+                let mut object = serde_json::Map::new();
+                for (key, value) in pairs {
+                    let key = match key {
+                        LuaValue::String(s) => s.to_string_lossy().to_string(),
+                        LuaValue::Integer(i) => i.to_string(),
+                        other => return Err(format!("{} keys cannot be encoded as JSON", other.type_name())),
+                    };
+                    object.insert(key, lua_to_json(value, depth + 1)?);
+                }
+                Value::Object(object)
+            }
+        }
+        other => return Err(format!("{} values cannot be encoded as JSON", other.type_name())),
+    })
+}
+
+/// Converts JSON to a Lua value. Objects become tables and arrays become sequences. `null` becomes `nil`,
+/// so nulls in arrays leave holes.
+fn json_to_lua(lua: &Lua, value: serde_json::Value) -> LuaResult<LuaValue<'_>> {
+    use serde_json::Value;
+
+    Ok(match value {
+        Value::Null => LuaValue::Nil,
+        Value::Bool(b) => LuaValue::Boolean(b),
+        // Luau integers are only 32 bits, larger integers have to be numbers
+        Value::Number(n) => match n.as_i64().and_then(|i| LuaInteger::try_from(i).ok()) {
+            Some(i) => LuaValue::Integer(i),
+            None => LuaValue::Number(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => LuaValue::String(lua.create_string(&s)?),
+        Value::Array(array) => {
+            let table = lua.create_table_with_capacity(array.len() as i32, 0)?;
+            for (i, value) in array.into_iter().enumerate() {
+                table.raw_set(i + 1, json_to_lua(lua, value)?)?;
+            }
+            LuaValue::Table(table)
+        }
+        Value::Object(object) => {
+            let table = lua.create_table_with_capacity(0, object.len() as i32)?;
+            for (key, value) in object {
+                table.raw_set(key, json_to_lua(lua, value)?)?;
+            }
+            LuaValue::Table(table)
+        }
+    })
+}
+
+/// Converts the arguments of the functions scripts use to change actions, e.g. `register_action`, checking that
+/// every key name is known.
+/// The keys are sorted and deduplicated since their order does not matter.
+fn action_args(lua: &Lua, name: LuaValue, keys: LuaValue) -> Result<(String, Vec<String>), String> {
+    let name = match name {
+        LuaValue::String(s) if !s.as_bytes().is_empty() => s.to_string_lossy().to_string(),
+        _ => return Err("action name must be a non-empty string".to_string()),
+    };
+
+    let mut keys = match keys {
+        LuaValue::Table(t) => t
+            .sequence_values::<LuaValue>()
+            .map(|v| match v {
+                Ok(LuaValue::String(s)) => Ok(s.to_string_lossy().to_string()),
+                _ => Err("keys must be a list of key names".to_string()),
+            })
+            .collect::<Result<Vec<String>, String>>()?,
+        _ => return Err("keys must be a list of key names".to_string()),
+    };
+    if keys.is_empty() {
+        return Err(format!("action {name} needs at least one key"));
     }
+    keys.sort();
+    keys.dedup();
+
+    let custom_key_names = lua
+        .named_registry_value::<_, LuaTable>(CUSTOM_KEY_NAMES)
+        .map_err(|e| e.to_string())?;
+    for key in keys.iter() {
+        if custom_key_names.contains_key(key.as_str()).unwrap_or(false)
+            || Modifier::from_name(key).is_some()
+        {
+            continue;
+        }
+
+        hotkey_listener::parse_key(key).map_err(|e| e.to_string())?;
+    }
+
+    Ok((name, keys))
 }
 
-pub fn lua() -> Result<Lua, Box<dyn Error>> {
+/// Creates the `Lua` state with every global available to scripts.
+///
+/// File access from scripts is limited to `data_dir`. Actions registered by scripts are sent to `commands`.
+/// Scripts running longer than `time_limit` in one call are stopped with an error.
+pub fn lua(
+    data_dir: &Path,
+    commands: Sender<ListenerCommand>,
+    time_limit: Duration,
+) -> Result<Lua, Box<dyn Error>> {
     let lua = Lua::new();
+    lua.set_app_data(DataDir(data_dir.to_path_buf()));
+
+    {
+        let time_limit = Rc::new(TimeLimit {
+            limit: Cell::new(time_limit),
+            deadline: Cell::new(None),
+        });
+        lua.set_app_data(time_limit.clone());
+
+        lua.set_interrupt(move || match time_limit.deadline.get() {
+            Some(deadline) if Instant::now() > deadline => Ok(LuaVmState::Yield),
+            _ => Ok(LuaVmState::Continue),
+        });
+    }
+
     {
         let globals = lua.globals();
 
         #[cfg(feature = "reqwest")]
         {
-            let request_constructor = lua.create_function(|_, ()| Ok(Request))?;
-            globals.set("reqwest", request_constructor)?;
+            // Every `Request` shares one client so connections are reused
+            let client = reqwest::blocking::Client::new();
+
+            // Takes an optional table of options, currently only `timeout` in seconds
+            let request_constructor = lua.create_function(move |lua, options: LuaValue| {
+                let timeout = match options {
+                    LuaValue::Table(options) => options.get::<_, LuaValue>("timeout"),
+                    LuaValue::Nil => Ok(LuaValue::Nil),
+                    _ => return Ok((false, LuaValue::String(lua.create_string("options must be a table")?))),
+                };
+                let timeout = match timeout {
+                    Ok(LuaValue::Integer(secs)) if secs > 0 => Duration::from_secs(secs as u64),
+                    Ok(LuaValue::Number(secs)) if secs > 0.0 && secs.is_finite() => {
+                        Duration::from_secs_f64(secs)
+                    }
+                    Ok(LuaValue::Nil) => DEFAULT_REQUEST_TIMEOUT,
+                    Ok(_) => {
+                        let error = "timeout must be a positive number of seconds";
+                        return Ok((false, LuaValue::String(lua.create_string(error)?)));
+                    }
+                    Err(e) => return Ok((false, LuaValue::String(lua.create_string(&e.to_string())?))),
+                };
+
+                let request = lua.create_userdata(Request {
+                    client: client.clone(),
+                    timeout,
+                })?;
+                Ok((true, LuaValue::UserData(request)))
+            })?;
+            globals.set("reqwest", raise_on_failure(&lua, request_constructor)?)?;
+        }
+
+        #[cfg(feature = "audio")]
+        {
+            let player = crate::audio::SoundPlayer::new();
+            let play_sound = lua.create_function(move |lua, path: LuaValue| {
+                match string_arg(lua, path, "sound path") {
+                    Ok(path) => {
+                        player.play(Path::new(&path));
+                        Ok((true, None))
+                    }
+                    Err(e) => Ok((false, Some(e))),
+                }
+            })?;
+            globals.set("play_sound", raise_on_failure(&lua, play_sound)?)?;
+        }
+
+        {
+            let time = lua.create_table()?;
+
+            // Monotonic, so only useful for measuring elapsed time
+            let start = Instant::now();
+            time.set(
+                "now_ms",
+                lua.create_function(move |_, ()| Ok(start.elapsed().as_secs_f64() * 1000.0))?,
+            )?;
+
+            time.set(
+                "unix",
+                lua.create_function(|_, ()| {
+                    Ok(SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs_f64())
+                })?,
+            )?;
+
+            globals.set("time", time)?;
+        }
+
+        {
+            lua.set_named_registry_value(ACTION_CALLBACKS, lua.create_table()?)?;
+
+            // Passing nil instead of a function removes the callback
+            let on_action = lua.create_function(|lua, (name, f): (LuaValue, LuaValue)| {
+                let name = match string_arg(lua, name, "action name") {
+                    Ok(name) => name,
+                    Err(e) => return Ok((false, Some(e))),
+                };
+                let f = match f {
+                    LuaValue::Function(f) => Some(f),
+                    LuaValue::Nil => None,
+                    _ => return Ok((false, Some("callback must be a function or nil".to_string()))),
+                };
+
+                match lua
+                    .named_registry_value::<_, LuaTable>(ACTION_CALLBACKS)
+                    .and_then(|callbacks| callbacks.set(name, f))
+                {
+                    Ok(_) => Ok((true, None)),
+                    Err(e) => Ok((false, Some(e.to_string()))),
+                }
+            })?;
+            globals.set("on_action", raise_on_failure(&lua, on_action)?)?;
+        }
+
+        {
+            lua.set_named_registry_value(CUSTOM_KEY_NAMES, lua.create_table()?)?;
+
+            // Registrations are applied later by the main loop, so duplicates are tracked here to report them
+            // to the script right away
+            let registered = Rc::new(RefCell::new(HashSet::<(String, Vec<String>)>::new()));
+
+            let (register_commands, register_registered) = (commands.clone(), registered.clone());
+            let register = lua.create_function(move |lua, (name, keys): (LuaValue, LuaValue)| {
+                let (name, keys) = match action_args(lua, name, keys) {
+                    Ok(args) => args,
+                    Err(e) => return Ok((false, Some(e))),
+                };
+
+                if !register_registered
+                    .borrow_mut()
+                    .insert((name.clone(), keys.clone()))
+                {
+                    return Ok((false, Some(format!("action {name} is already registered to those keys"))));
+                }
+
+                match register_commands.send(ListenerCommand::Register { name, keys }) {
+                    Ok(_) => Ok((true, None)),
+                    Err(_) => Ok((false, Some("hotkey listener is not running".to_string()))),
+                }
+            })?;
+            globals.set("register_action", raise_on_failure(&lua, register)?)?;
+
+            // Works for any action, so unlike unregistering there is nothing to check here
+            for (global, enabled) in [("enable_action", true), ("disable_action", false)] {
+                let commands = commands.clone();
+                let set_enabled =
+                    lua.create_function(move |lua, (name, keys): (LuaValue, LuaValue)| {
+                        let (name, keys) = match action_args(lua, name, keys) {
+                            Ok(args) => args,
+                            Err(e) => return Ok((false, Some(e))),
+                        };
+
+                        match commands.send(ListenerCommand::SetEnabled {
+                            name,
+                            keys,
+                            enabled,
+                        }) {
+                            Ok(_) => Ok((true, None)),
+                            Err(_) => {
+                                Ok((false, Some("hotkey listener is not running".to_string())))
+                            }
+                        }
+                    })?;
+                globals.set(global, raise_on_failure(&lua, set_enabled)?)?;
+            }
+
+            let unregister = lua.create_function(move |lua, (name, keys): (LuaValue, LuaValue)| {
+                let (name, keys) = match action_args(lua, name, keys) {
+                    Ok(args) => args,
+                    Err(e) => return Ok((false, Some(e))),
+                };
+
+                if !registered.borrow_mut().remove(&(name.clone(), keys.clone())) {
+                    return Ok((false, Some(format!("action {name} is not registered to those keys by a script"))));
+                }
+
+                match commands.send(ListenerCommand::Unregister { name, keys }) {
+                    Ok(_) => Ok((true, None)),
+                    Err(_) => Ok((false, Some("hotkey listener is not running".to_string()))),
+                }
+            })?;
+            globals.set("unregister_action", raise_on_failure(&lua, unregister)?)?;
+        }
+
+        {
+            let log = lua.create_table()?;
+
+            for (name, level) in [
+                ("error", log::Level::Error),
+                ("warn", log::Level::Warn),
+                ("info", log::Level::Info),
+                ("debug", log::Level::Debug),
+            ] {
+                log.set(
+                    name,
+                    lua.create_function(move |lua, args: LuaMultiValue| {
+                        let message = log_message(lua, args);
+                        match lua.named_registry_value::<_, Option<String>>(CURRENT_ACTION) {
+                            Ok(Some(action)) => log::log!(target: "lua", level, "[{action}] {message}"),
+                            _ => log::log!(target: "lua", level, "{message}"),
+                        }
+
+                        Ok(())
+                    })?,
+                )?;
+            }
+
+            globals.set("log", log)?;
+        }
+
+        {
+            let json = lua.create_table()?;
+
+            let encode = lua.create_function(|_, value: LuaValue| {
+                match lua_to_json(value, 0).and_then(|v| serde_json::to_string(&v).map_err(|e| e.to_string())) {
+                    Ok(s) => Ok((true, s)),
+                    Err(e) => Ok((false, e)),
+                }
+            })?;
+            json.set("encode", raise_on_failure(&lua, encode)?)?;
+
+            let decode = lua.create_function(|lua, s: LuaValue| {
+                let s = match s {
+                    LuaValue::String(s) => s,
+                    _ => return Ok((false, LuaValue::String(lua.create_string("can only decode strings")?))),
+                };
+                match serde_json::from_slice::<serde_json::Value>(s.as_bytes()) {
+                    Ok(v) => Ok((true, json_to_lua(lua, v)?)),
+                    Err(e) => Ok((false, LuaValue::String(lua.create_string(&e.to_string())?))),
+                }
+            })?;
+            json.set("decode", raise_on_failure(&lua, decode)?)?;
+
+            globals.set("json", json)?;
+        }
+
+        {
+            let fs = lua.create_table()?;
+
+            // Bad arguments are raised as errors, files that cannot be written to only return false
+            let data_dir = data_dir.to_path_buf();
+            let append = lua.create_function(move |lua, (path, text): (LuaValue, LuaValue)| {
+                let (path, text) = match (string_arg(lua, path, "path"), string_arg(lua, text, "text")) {
+                    (Ok(path), Ok(text)) => (path, text),
+                    (Err(e), _) | (_, Err(e)) => {
+                        return Ok((false, LuaValue::String(lua.create_string(&e)?)))
+                    }
+                };
+
+                let path = Path::new(&path);
+                if !path
+                    .components()
+                    .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+                {
+                    log::warn!("{} is outside of the data directory", path.display());
+                    return Ok((true, LuaValue::Boolean(false)));
+                }
+
+                let path = data_dir.join(path);
+                match crate::sink::append_to_file(&path, &text) {
+                    Ok(_) => Ok((true, LuaValue::Boolean(true))),
+                    Err(e) => {
+                        log::warn!("Unable to append to {}: {e}", path.display());
+                        Ok((true, LuaValue::Boolean(false)))
+                    }
+                }
+            })?;
+            fs.set("append", raise_on_failure(&lua, append)?)?;
+
+            globals.set("fs", fs)?;
         }
     }
 
     Ok(lua)
 }
+
+/// Loads every `*.lua` file in `dir`, in alphabetical order, into the same `Lua` state.
+///
+/// Since the state is shared, anything defined by an earlier script is visible to later scripts.
+/// Loading happens in two phases: first every script is executed, then every global `init()` function
+/// that a script defined is called in load order. This lets scripts depend on each other's definitions
+/// regardless of file order.
+///
+/// A script that fails to load or whose `init()` fails is logged and skipped. Returns the scripts that failed.
+pub fn load_scripts(lua: &Lua, dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut paths = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lua"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+
+    let globals = lua.globals();
+    let mut init_functions = vec![];
+    let mut failed = vec![];
+
+    for path in paths.iter() {
+        let result = std::fs::read_to_string(path)
+            .map_err(LuaError::external)
+            .and_then(|script| {
+                lua.load(&script)
+                    .set_name(path.to_string_lossy())?
+                    .into_function()
+            })
+            .and_then(|f| call_with_time_limit::<_, ()>(lua, f, ()));
+
+        // Take ownership of the init function so the next script can define its own. A failed script may still
+        // have defined one, which is dropped with the rest of the script.
+        let init = globals.get::<_, LuaValue>(INIT_FUNCTION)?;
+        globals.set(INIT_FUNCTION, LuaValue::Nil)?;
+
+        match (result, init) {
+            (Err(e), _) => {
+                log::error!("Unable to load script {}: {}", path.display(), e);
+                failed.push(path.clone());
+            }
+            (Ok(_), LuaValue::Function(f)) => init_functions.push((path, f)),
+            (Ok(_), _) => {}
+        }
+    }
+
+    for (path, f) in init_functions {
+        if let Err(e) = call_with_time_limit::<_, ()>(lua, f, ()) {
+            log::error!("Unable to run init() of script {}: {}", path.display(), e);
+            failed.push(path.clone());
+        }
+    }
+
+    Ok(failed)
+}
+
+/// Creates a fresh `Lua` state with the same settings as `old` and loads the scripts in `dir` into it, see
+/// `load_scripts`. Nothing defined by the scripts in `old`, like `on_action` functions, is carried over.
+///
+/// Actions registered by the new scripts are sent to the returned receiver instead of the one used by `old`.
+/// If loading fails, nothing was sent anywhere and `old` can keep being used as is.
+pub fn reload(old: &Lua, dir: &Path) -> Result<(Lua, Receiver<ListenerCommand>), Box<dyn Error>> {
+    let data_dir = match old.app_data_ref::<DataDir>() {
+        Some(d) => d.0.clone(),
+        None => return Err("Lua state was not created by scripting::lua".into()),
+    };
+    let time_limit = match old.app_data_ref::<Rc<TimeLimit>>() {
+        Some(t) => t.limit.get(),
+        None => return Err("Lua state was not created by scripting::lua".into()),
+    };
+
+    let (sender, receiver) = unbounded();
+    let lua = lua(&data_dir, sender, time_limit)?;
+
+    let custom_key_names = old
+        .named_registry_value::<_, LuaTable>(CUSTOM_KEY_NAMES)?
+        .pairs::<String, LuaValue>()
+        .filter_map(|pair| pair.ok().map(|(name, _)| name))
+        .collect::<Vec<String>>();
+    set_custom_key_names(&lua, &custom_key_names.iter().collect::<Vec<&String>>())?;
+
+    let failed = load_scripts(&lua, dir)?;
+    if !failed.is_empty() {
+        return Err(format!("{} scripts failed to load", failed.len()).into());
+    }
+
+    Ok((lua, receiver))
+}
+
+/// Calls the function registered with `on_action` for the fired action, if there is one. The function is
+/// passed a table with the action's `name`, the `keys` that fired it, and the unix `timestamp` in milliseconds.
+///
+/// Returns whether a function was called.
+pub fn dispatch(lua: &Lua, event: &ActionEvent) -> Result<bool, Box<dyn Error>> {
+    let callbacks = lua.named_registry_value::<_, LuaTable>(ACTION_CALLBACKS)?;
+    let f = match callbacks.get::<_, Option<LuaFunction>>(event.name.as_str())? {
+        Some(f) => f,
+        None => return Ok(false),
+    };
+
+    let table = lua.create_table()?;
+    table.set("name", event.name.as_str())?;
+    table.set("keys", event.keys.clone())?;
+    table.set("timestamp", event.timestamp)?;
+
+    // Lets `log` functions say which action they were called for
+    lua.set_named_registry_value(CURRENT_ACTION, event.name.as_str())?;
+    let result = call_with_time_limit::<_, ()>(lua, f, table);
+    lua.unset_named_registry_value(CURRENT_ACTION)?;
+    result?;
+
+    Ok(true)
+}
+
+/// Sets the key names that scripts may use besides the built-in ones, i.e. the config's aliases and key groups.
+/// Replaces the previous names.
+pub fn set_custom_key_names(lua: &Lua, names: &[&String]) -> Result<(), Box<dyn Error>> {
+    let table = lua.create_table()?;
+    for name in names.iter() {
+        table.set(name.as_str(), true)?;
+    }
+
+    lua.set_named_registry_value(CUSTOM_KEY_NAMES, table)?;
+
+    Ok(())
+}
+
+/// Changes how long scripts may run in one call, see `lua()`.
+pub fn set_time_limit(lua: &Lua, time_limit: Duration) {
+    if let Some(t) = lua.app_data_ref::<Rc<TimeLimit>>() {
+        t.limit.set(time_limit);
+    }
+}
+
+/// Lists every action that has a function registered with `on_action`.
+pub fn action_callback_names(lua: &Lua) -> Result<Vec<String>, Box<dyn Error>> {
+    let callbacks = lua.named_registry_value::<_, LuaTable>(ACTION_CALLBACKS)?;
+
+    Ok(callbacks
+        .pairs::<String, LuaValue>()
+        .filter_map(|pair| pair.ok().map(|(name, _)| name))
+        .collect())
+}
+
+/// Reads Lua from stdin one line at a time and evaluates it against `lua`, printing the results.
+///
+/// Each line is first tried as an expression so `1 + 1` prints `2`, then as a statement. Errors are printed
+/// instead of ending the REPL. Ends on EOF.
+pub fn repl(lua: &Lua) -> Result<(), Box<dyn Error>> {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+
+    loop {
+        print!("> ");
+        stdout.flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let result = lua
+            .load(&format!("return {line}"))
+            .set_name("repl")?
+            .into_function()
+            .or_else(|_| lua.load(line).set_name("repl")?.into_function())
+            .and_then(|f| call_with_time_limit::<_, LuaMultiValue>(lua, f, ()));
+
+        match result {
+            Ok(values) => {
+                if values.is_empty() {
+                    continue;
+                }
+
+                let to_string = lua.globals().get::<_, LuaFunction>("tostring")?;
+                let values = values
+                    .into_iter()
+                    .map(|v| to_string.call::<_, String>(v))
+                    .collect::<LuaResult<Vec<String>>>()?;
+
+                println!("{}", values.join("\t"));
+            }
+            Err(e) => println!("error: {e}"),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_arg_rejects_other_values() {
+        let lua = Lua::new();
+
+        assert_eq!(
+            string_arg(&lua, LuaValue::String(lua.create_string("x").unwrap()), "name"),
+            Ok("x".to_string())
+        );
+        assert_eq!(
+            string_arg(&lua, LuaValue::Table(lua.create_table().unwrap()), "name"),
+            Err("name must be a string".to_string())
+        );
+        assert_eq!(
+            string_arg(&lua, LuaValue::String(lua.create_string(&[0xff]).unwrap()), "name"),
+            Err("name must be valid UTF-8".to_string())
+        );
+    }
+
+    fn test_lua(data_dir: &Path) -> Lua {
+        let (sender, _) = unbounded();
+        lua(data_dir, sender, Duration::from_secs(1)).unwrap()
+    }
+
+    /// Calls `code` in protected mode, returning the error it raised or its first return value.
+    fn pcall<'lua>(lua: &'lua Lua, code: &str) -> (bool, LuaValue<'lua>) {
+        lua.load(&format!("return pcall(function() return {code} end)"))
+            .eval::<(bool, LuaValue)>()
+            .unwrap()
+    }
+
+    /// Converts a Lua error message to a `String`, failing if the value is not one.
+    fn message(value: LuaValue) -> String {
+        match value {
+            LuaValue::String(s) => s.to_string_lossy().to_string(),
+            other => panic!("expected an error message, got {other:?}"),
+        }
+    }
+
+    /// Runs `code`, which has to raise an error, and returns the message without the location Lua adds to it.
+    fn raised(lua: &Lua, code: &str) -> String {
+        let error = match pcall(lua, code) {
+            (false, error) => message(error),
+            (true, value) => panic!("{code} returned {value:?} instead of raising an error"),
+        };
+
+        match error
+            .split_once("]:")
+            .and_then(|(_, rest)| rest.split_once(": "))
+        {
+            Some((_, message)) => message.to_string(),
+            None => error,
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    #[test]
+    fn play_sound_rejects_bad_arguments() {
+        let lua = test_lua(&std::env::temp_dir());
+
+        assert_eq!(raised(&lua, "play_sound({})"), "sound path must be a string");
+
+        // Missing files are only logged by the audio thread, the script carries on
+        assert!(pcall(&lua, r#"play_sound("missing.wav")"#).0);
+    }
+
+    #[test]
+    fn on_action_rejects_bad_arguments() {
+        let lua = test_lua(&std::env::temp_dir());
+
+        assert_eq!(
+            raised(&lua, "on_action({}, nil)"),
+            "action name must be a string"
+        );
+        assert_eq!(
+            raised(&lua, r#"on_action("wave", 1)"#),
+            "callback must be a function or nil"
+        );
+
+        assert!(pcall(&lua, r#"on_action("wave", function() end)"#).0);
+        assert_eq!(action_callback_names(&lua).unwrap(), ["wave"]);
+        assert!(pcall(&lua, r#"on_action("wave", nil)"#).0);
+        assert!(action_callback_names(&lua).unwrap().is_empty());
+    }
+
+    #[test]
+    fn dispatch_calls_the_function_for_the_action() {
+        let lua = test_lua(&std::env::temp_dir());
+        lua.load(
+            r#"on_action("wave", function(event)
+                fired = event.name .. " " .. table.concat(event.keys, "+")
+            end)"#,
+        )
+        .exec()
+        .unwrap();
+
+        let event = |name: &str| ActionEvent {
+            name: name.to_string(),
+            keys: vec!["ControlLeft".to_string(), "KeyW".to_string()],
+            timestamp: 0,
+            at: Instant::now(),
+        };
+        assert!(!dispatch(&lua, &event("jump")).unwrap());
+        assert!(dispatch(&lua, &event("wave")).unwrap());
+        assert_eq!(
+            lua.globals().get::<_, String>("fired").unwrap(),
+            "wave ControlLeft+KeyW"
+        );
+    }
+
+    #[test]
+    fn enabling_actions_is_queued_for_the_listener() {
+        let (sender, commands) = unbounded();
+        let lua = lua(&std::env::temp_dir(), sender, Duration::from_secs(1)).unwrap();
+
+        lua.load(r#"disable_action("wave", { "KeyW", "ControlLeft" })"#)
+            .exec()
+            .unwrap();
+        lua.load(r#"enable_action("wave", { "KeyW", "ControlLeft" })"#)
+            .exec()
+            .unwrap();
+
+        let queued = commands
+            .try_iter()
+            .map(|command| match command {
+                ListenerCommand::SetEnabled {
+                    name,
+                    keys,
+                    enabled,
+                } => (name, keys, enabled),
+                other => panic!("unexpected {other:?}"),
+            })
+            .collect::<Vec<_>>();
+        let keys = vec!["ControlLeft".to_string(), "KeyW".to_string()];
+        assert_eq!(
+            queued,
+            [
+                ("wave".to_string(), keys.clone(), false),
+                ("wave".to_string(), keys, true)
+            ]
+        );
+
+        assert_eq!(
+            raised(&lua, r#"disable_action("wave", {})"#),
+            "action wave needs at least one key"
+        );
+    }
+
+    /// Answers one request with `response`, returning the URL to send it to and a handle that gives back the
+    /// request as it was received.
+    #[cfg(feature = "reqwest")]
+    fn serve_once(response: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        use std::io::{Read, Write};
+
+        let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", server.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = server.accept().unwrap();
+            let mut request = vec![];
+            let mut buf = [0; 1024];
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_lowercase();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .map_or(0, |l| l.trim().parse().unwrap());
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        (url, handle)
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn requests_send_headers_and_return_the_status() {
+        let lua = test_lua(&std::env::temp_dir());
+
+        let (url, request) = serve_once(
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 7\r\nConnection: close\r\n\r\nmissing",
+        );
+        let result = lua
+            .load(&format!(
+                r#"return reqwest():post("{url}", "hello", {{ Authorization = "Bearer token" }})"#
+            ))
+            .eval::<LuaTable>()
+            .unwrap();
+        assert!(!result.get::<_, bool>("ok").unwrap());
+        assert_eq!(result.get::<_, u16>("status").unwrap(), 404);
+        assert_eq!(result.get::<_, String>("body").unwrap(), "missing");
+        assert!(!result.contains_key("error").unwrap());
+
+        let request = request.join().unwrap();
+        assert!(request.starts_with("POST / HTTP/1.1\r\n"), "{request}");
+        assert!(request.contains("\r\nauthorization: Bearer token\r\n"), "{request}");
+        assert!(request.ends_with("\r\n\r\nhello"), "{request}");
+
+        let (url, request) =
+            serve_once("HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok");
+        let result = lua
+            .load(&format!(r#"return reqwest():get("{url}", {{ Accept = "text/plain" }})"#))
+            .eval::<LuaTable>()
+            .unwrap();
+        assert!(result.get::<_, bool>("ok").unwrap());
+        assert_eq!(result.get::<_, u16>("status").unwrap(), 200);
+        assert_eq!(result.get::<_, String>("body").unwrap(), "ok");
+
+        let request = request.join().unwrap();
+        assert!(request.starts_with("GET / HTTP/1.1\r\n"), "{request}");
+        assert!(request.contains("\r\naccept: text/plain\r\n"), "{request}");
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn requests_with_bad_arguments_fail_without_being_sent() {
+        let lua = test_lua(&std::env::temp_dir());
+
+        for (code, expected) in [
+            ("reqwest():get({})", "url must be a string"),
+            (r#"reqwest():get("http://localhost", 1)"#, "headers must be a table"),
+            (r#"reqwest():get("http://localhost", { Accept = {} })"#, "header value must be a string"),
+            (r#"reqwest():post("http://localhost")"#, "body must be a string"),
+            (r#"reqwest():post(1, "body")"#, "url must be a string"),
+        ] {
+            let result = lua.load(code).eval::<LuaTable>().unwrap();
+            assert!(!result.get::<_, bool>("ok").unwrap(), "{code} should fail");
+            assert_eq!(result.get::<_, String>("error").unwrap(), expected);
+        }
+
+        // Invalid URLs are only found when sending, and are reported the same way
+        let result = lua.load(r#"reqwest():get("not a url")"#).eval::<LuaTable>().unwrap();
+        assert!(!result.get::<_, bool>("ok").unwrap());
+        assert_eq!(result.get::<_, String>("error").unwrap(), "builder error: relative URL without a base");
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn reqwest_rejects_bad_options() {
+        let lua = test_lua(&std::env::temp_dir());
+
+        for (code, expected) in [
+            ("reqwest(5)", "options must be a table"),
+            (r#"reqwest({ timeout = "5" })"#, "timeout must be a positive number of seconds"),
+            ("reqwest({ timeout = 0 })", "timeout must be a positive number of seconds"),
+            ("reqwest({ timeout = -1.5 })", "timeout must be a positive number of seconds"),
+            ("reqwest({ timeout = math.huge })", "timeout must be a positive number of seconds"),
+        ] {
+            assert_eq!(raised(&lua, code), expected, "{code}");
+        }
+
+        for code in ["reqwest()", "reqwest({})", "reqwest({ timeout = 2 })", "reqwest({ timeout = 0.5 })"] {
+            assert!(matches!(pcall(&lua, code), (true, LuaValue::UserData(_))), "{code} should succeed");
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn requests_that_take_too_long_time_out() {
+        let lua = test_lua(&std::env::temp_dir());
+
+        // Connections are accepted by the OS but never answered
+        let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", server.local_addr().unwrap());
+
+        let start = Instant::now();
+        let result = lua
+            .load(&format!(r#"return reqwest({{ timeout = 0.2 }}):get("{url}")"#))
+            .eval::<LuaTable>()
+            .unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(!result.get::<_, bool>("ok").unwrap());
+        assert!(result.get::<_, bool>("timed_out").unwrap());
+        assert!(!result.contains_key("status").unwrap());
+        assert!(result.contains_key("error").unwrap());
+    }
+
+    #[test]
+    fn fs_append_rejects_bad_arguments() {
+        let dir = crate::utils::test_dir("fs-append");
+        let lua = test_lua(&dir);
+
+        assert_eq!(
+            raised(&lua, r#"fs.append({}, "y")"#),
+            "path must be a string"
+        );
+        assert_eq!(raised(&lua, r#"fs.append("x")"#), "text must be a string");
+
+        assert!(matches!(
+            pcall(&lua, r#"fs.append("../x", "y")"#),
+            (true, LuaValue::Boolean(false))
+        ));
+        assert!(matches!(
+            pcall(&lua, r#"fs.append("x", "y")"#),
+            (true, LuaValue::Boolean(true))
+        ));
+        assert_eq!(std::fs::read_to_string(dir.join("x")).unwrap(), "y");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crossbeam_channel::{unbounded, Sender};
+use log::{debug, error, warn};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub enum Error {
+    Disconnected,
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Something that receives the name of every action that is fired.
+pub trait ActionSink {
+    /// Hands a fired action off to the sink. Sinks should not block the caller.
+    fn send(&mut self, action_name: &str) -> Result<()>;
+
+    /// Whether the sink does anything with the given action. Defaults to every action.
+    fn handles(&self, _action_name: &str) -> bool {
+        true
+    }
+}
+
+/// Writes every fired action name, newline-delimited, to a FIFO/named pipe.
+///
+/// Writing happens on a dedicated thread since opening a FIFO for writing blocks until
+/// a reader is present. If the reader goes away, the pipe is reopened instead of erroring out.
+pub struct FifoSink {
+    sender: Sender<String>,
+}
+
+impl FifoSink {
+    pub fn new(path: &Path) -> Self {
+        let (sender, receiver) = unbounded::<String>();
+        let path = path.to_path_buf();
+
+        thread::spawn(move || {
+            let mut pipe = None;
+
+            for line in receiver.iter() {
+                // Try once with the current pipe and once more with a freshly opened one
+                for _ in 0..2 {
+                    let file = match pipe.as_mut() {
+                        Some(f) => f,
+                        None => match open_fifo(&path) {
+                            Some(f) => pipe.insert(f),
+                            None => break,
+                        },
+                    };
+
+                    match file.write_all(line.as_bytes()).and_then(|_| file.flush()) {
+                        Ok(_) => break,
+                        Err(e) => {
+                            debug!("Reader disconnected from {}: {e}", path.display());
+                            pipe = None;
+                        }
+                    }
+                }
+            }
+        });
+
+        FifoSink { sender }
+    }
+}
+
+impl ActionSink for FifoSink {
+    fn send(&mut self, action_name: &str) -> Result<()> {
+        match self.sender.send(format!("{action_name}\n")) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(Error::Disconnected),
+        }
+    }
+}
+
+/// Appends a line to a file when an action fires.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppendFile {
+    pub path: PathBuf,
+    /// The line to append. `{time}` is replaced with the unix timestamp in seconds and `{action}`
+    /// with the action name. Defaults to `{time} {action}`.
+    #[serde(default = "AppendFile::default_template")]
+    pub template: String,
+}
+
+impl AppendFile {
+    fn default_template() -> String {
+        "{time} {action}".to_string()
+    }
+}
+
+/// Appends a line to a file for every action that has an `AppendFile` configured. Other actions are ignored.
+///
+/// Writing happens on a dedicated thread so slow disks don't hold up polling. Failures are logged and the
+/// line is dropped.
+pub struct AppendFileSink {
+    files: HashMap<String, AppendFile>,
+    sender: Sender<(PathBuf, String)>,
+}
+
+impl AppendFileSink {
+    pub fn new(files: HashMap<String, AppendFile>) -> Self {
+        let (sender, receiver) = unbounded::<(PathBuf, String)>();
+
+        thread::spawn(move || {
+            for (path, line) in receiver.iter() {
+                if let Err(e) = append_to_file(&path, &line) {
+                    warn!("Unable to append to {}: {e}", path.display());
+                }
+            }
+        });
+
+        AppendFileSink { files, sender }
+    }
+}
+
+impl ActionSink for AppendFileSink {
+    fn send(&mut self, action_name: &str) -> Result<()> {
+        let file = match self.files.get(action_name) {
+            Some(f) => f,
+            None => return Ok(()),
+        };
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let line = file
+            .template
+            .replace("{time}", &time.to_string())
+            .replace("{action}", action_name);
+
+        match self.sender.send((file.path.clone(), format!("{line}\n"))) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(Error::Disconnected),
+        }
+    }
+
+    fn handles(&self, action_name: &str) -> bool {
+        self.files.contains_key(action_name)
+    }
+}
+
+/// Appends text to a file, creating the file if it does not exist. The parent directory must exist.
+pub fn append_to_file(path: &Path, text: &str) -> std::io::Result<()> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(text.as_bytes())
+}
+
+/// Opens a FIFO for writing. Blocks until a reader opens the other end.
+fn open_fifo(path: &PathBuf) -> Option<std::fs::File> {
+    match OpenOptions::new().write(true).open(path) {
+        Ok(f) => Some(f),
+        Err(e) => {
+            error!("Unable to open {}: {e}", path.display());
+            None
+        }
+    }
+}
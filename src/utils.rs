@@ -1,9 +1,11 @@
+use std::{ffi::OsString, io::Write, path::Path};
+
 /// Gate multiple `pub mod` and `pub use` statements behind a single feature.
 macro_rules! feature_gate {
     (
         feature: $feature:literal,
-        mods: {$($m:ident,),*},
-        uses: {$($u:path,),*}
+        mods: {$($m:ident),* $(,)?},
+        uses: {$($u:path),* $(,)?}
     ) => {
         $(
             #[cfg(feature = $feature)]
@@ -17,3 +19,88 @@ macro_rules! feature_gate {
 }
 
 pub(crate) use feature_gate;
+
+/// Writes `bytes` to `path` without ever leaving a partially written file behind. The bytes are written to
+/// `<path>.tmp` first and then renamed over `path`. The previous contents of `path`, if any, are kept in
+/// `<path>.bak`, replacing any older backup.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let with_suffix = |suffix: &str| {
+        let mut p: OsString = path.as_os_str().to_owned();
+        p.push(suffix);
+        p
+    };
+
+    let tmp_path = with_suffix(".tmp");
+    let mut file = std::fs::File::create(&tmp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+
+    if path.exists() {
+        std::fs::copy(path, with_suffix(".bak"))?;
+    }
+
+    std::fs::rename(&tmp_path, path)
+}
+
+/// Creates an empty directory for a test under the system temp directory. `name` has to be unique between tests
+/// since they run in parallel.
+#[cfg(test)]
+pub fn test_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("viraction-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files_in(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn write_atomic_creates_new_file() {
+        let dir = test_dir("write-atomic-new");
+        let path = dir.join("config.toml");
+
+        write_atomic(&path, b"first").unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"first");
+        assert_eq!(files_in(&dir), ["config.toml"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn write_atomic_backs_up_previous_contents() {
+        let dir = test_dir("write-atomic-overwrite");
+        let path = dir.join("config.toml");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(
+            std::fs::read(dir.join("config.toml.bak")).unwrap(),
+            b"first"
+        );
+
+        write_atomic(&path, b"third").unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"third");
+        assert_eq!(
+            std::fs::read(dir.join("config.toml.bak")).unwrap(),
+            b"second"
+        );
+        assert_eq!(files_in(&dir), ["config.toml", "config.toml.bak"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}